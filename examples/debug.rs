@@ -11,9 +11,14 @@ fn main() {
 	if joysticks.len() == 0 {
 		panic!("No joysticks plugged in")
 	}
-	loop {
-		for event in joysticks.iter_mut().flat_map(|js| js.iter()) {
-			println!("{:?}", event)
+	let mut waiter = Waiter::new();
+	while let Some((index, result)) = waiter.wait(&mut joysticks, None) {
+		match result {
+			Ok(event) => println!("#{}: {:?}", joysticks[index].get_index(), event),
+			Err(err) => {
+				println!("#{}: {}", joysticks[index].get_index(), err);
+				joysticks.remove(index);
+			}
 		}
 	}
 }
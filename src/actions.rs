@@ -0,0 +1,504 @@
+use {Axis, Button, Error, Event, Joystick, StatefulJoystick};
+use {MAX_AXIS_VALUE, MIN_AXIS_VALUE};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// How far an axis has to be pushed, as a fraction of its range, for an action
+/// bound to it to be active or for a `BindingListener` to pick it
+pub static THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// An input an action can be bound to
+///
+/// Bindings are written as the names of their inputs, like `a`, `leftx`,
+/// `leftx,lefty` for a pair of axes or `a+leftshoulder` for a chord. The buttons
+/// of a chord are put in the order of `Button` when it is parsed or bound, so the
+/// same chord is always the same binding. A chord of one button is the same as
+/// that button, and a chord without any buttons can't be parsed or bound.
+///
+/// ``` rust
+/// use reminisce::{Axis, Button};
+/// use reminisce::actions::Binding;
+/// let chord = Binding::Chord(vec![Button::A, Button::LeftShoulder]);
+/// assert_eq!(chord.to_string(), "a+leftshoulder");
+/// assert_eq!("leftshoulder+a".parse::<Binding>().unwrap(), chord);
+/// assert_eq!("leftx, lefty".parse::<Binding>().unwrap(), Binding::AxisPair(Axis::LeftX, Axis::LeftY));
+/// assert_eq!("triggerright".parse::<Binding>().unwrap(), Binding::Axis(Axis::TriggerRight));
+/// assert_eq!("a+a".parse::<Binding>().unwrap(), Binding::Button(Button::A));
+/// assert!("".parse::<Binding>().is_err() && "+".parse::<Binding>().is_err());
+/// ```
+pub enum Binding {
+	/// A button
+	Button(Button),
+	/// Buttons that all have to be pressed at once
+	Chord(Vec<Button>),
+	/// An axis, which is active when it is pushed past the `THRESHOLD`
+	Axis(Axis),
+	/// The horizontal and vertical axes of a stick, as a two dimensional vector
+	AxisPair(Axis, Axis)
+}
+
+/// Get how far an axis is pushed as a fraction, which is between -1 and 1 for
+/// sticks and between 0 and 1 for triggers, which rest at `MIN_AXIS_VALUE`
+fn axis_fraction(axis: Axis, value: i16) -> f32 {
+	match axis {
+		Axis::TriggerLeft | Axis::TriggerRight => {
+			let range = MAX_AXIS_VALUE as f32 - MIN_AXIS_VALUE as f32;
+			((value as f32 - MIN_AXIS_VALUE as f32) / range).max(0.0).min(1.0)
+		},
+		_ => (value as f32 / MAX_AXIS_VALUE as f32).max(-1.0).min(1.0)
+	}
+}
+
+/// Get the other axis of the stick an axis is part of, with the horizontal axis first
+fn stick_pair(axis: Axis) -> Option<(Axis, Axis)> {
+	match axis {
+		Axis::LeftX | Axis::LeftY => Some((Axis::LeftX, Axis::LeftY)),
+		Axis::RightX | Axis::RightY => Some((Axis::RightX, Axis::RightY)),
+		Axis::TriggerLeft | Axis::TriggerRight => None
+	}
+}
+
+impl Binding {
+	/// Put the buttons of a chord in order and drop repeated ones, turning a chord
+	/// of one button into that button
+	fn normalise(self) -> Result<Binding, Error> {
+		match self {
+			Binding::Chord(mut buttons) => {
+				buttons.sort();
+				buttons.dedup();
+				match buttons.len() {
+					0 => Err(Error::InvalidBinding("a chord needs at least one button".to_string())),
+					1 => Ok(Binding::Button(buttons[0])),
+					_ => Ok(Binding::Chord(buttons))
+				}
+			},
+			binding => Ok(binding)
+		}
+	}
+
+	/// Get how far an axis of a joystick is pushed as a fraction
+	fn get_fraction<J>(joystick: &J, axis: Axis) -> f32 where J:StatefulJoystick {
+		axis_fraction(axis, joystick.get_axis(axis).unwrap_or(match axis {
+			Axis::TriggerLeft | Axis::TriggerRight => MIN_AXIS_VALUE,
+			_ => 0
+		}))
+	}
+
+	/// Get the value of this binding on a joystick as a vector
+	///
+	/// Axis pairs are clamped to the unit circle. Other bindings only have an x
+	/// component, which is 1 for buttons and chords that are pressed.
+	pub fn get_vector<J>(&self, joystick: &J) -> (f32, f32) where J:StatefulJoystick {
+		let pressed = |button: &Button| joystick.get_button(*button).unwrap_or(false);
+		match *self {
+			Binding::Button(ref button) => (if pressed(button) { 1.0 } else { 0.0 }, 0.0),
+			Binding::Chord(ref buttons) => (if !buttons.is_empty() && buttons.iter().all(pressed) { 1.0 } else { 0.0 }, 0.0),
+			Binding::Axis(axis) => (Binding::get_fraction(joystick, axis), 0.0),
+			Binding::AxisPair(x_axis, y_axis) => {
+				let (x, y) = (Binding::get_fraction(joystick, x_axis), Binding::get_fraction(joystick, y_axis));
+				let magnitude = (x * x + y * y).sqrt();
+				if magnitude > 1.0 { (x / magnitude, y / magnitude) } else { (x, y) }
+			}
+		}
+	}
+
+	/// Get the value of this binding on a joystick
+	///
+	/// This is how far an axis is pushed, how far a pair of axes is pushed from the
+	/// centre, or 1 for buttons and chords that are pressed.
+	pub fn get_value<J>(&self, joystick: &J) -> f32 where J:StatefulJoystick {
+		match self.get_vector(joystick) {
+			(x, y) if y == 0.0 => x,
+			(x, y) => (x * x + y * y).sqrt()
+		}
+	}
+
+	/// Check if this binding is active on a joystick
+	pub fn is_active<J>(&self, joystick: &J) -> bool where J:StatefulJoystick {
+		self.get_value(joystick).abs() > THRESHOLD
+	}
+}
+
+impl FromStr for Binding {
+	type Err = Error;
+	fn from_str(text: &str) -> Result<Binding, Error> {
+		let text = text.trim();
+		if let Some(comma) = text.find(',') {
+			let x = try!(text[..comma].trim().parse().map_err(Error::InvalidBinding));
+			let y = try!(text[comma + 1..].trim().parse().map_err(Error::InvalidBinding));
+			Ok(Binding::AxisPair(x, y))
+		} else if text.contains('+') {
+			let mut buttons = Vec::new();
+			for button in text.split('+') {
+				buttons.push(try!(button.trim().parse().map_err(Error::InvalidBinding)));
+			}
+			Binding::Chord(buttons).normalise()
+		} else if let Ok(button) = text.parse() {
+			Ok(Binding::Button(button))
+		} else if let Ok(axis) = text.parse() {
+			Ok(Binding::Axis(axis))
+		} else {
+			Err(Error::InvalidBinding(format!("no button or axis called {}", text)))
+		}
+	}
+}
+
+impl fmt::Display for Binding {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Binding::Button(button) => write!(fmt, "{}", button),
+			Binding::Chord(ref buttons) => {
+				for (index, button) in buttons.iter().enumerate() {
+					try!(write!(fmt, "{}{}", if index == 0 { "" } else { "+" }, button));
+				}
+				Ok(())
+			},
+			Binding::Axis(axis) => write!(fmt, "{}", axis),
+			Binding::AxisPair(x, y) => write!(fmt, "{},{}", x, y)
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Named actions and the inputs they are bound to
+///
+/// An action can be bound to more than one input, and is active when any of them
+/// is. A map is written as text with a line for every action, with its name, a
+/// colon and its bindings separated by `|`. Blank lines and lines starting with
+/// `#` are skipped when parsing.
+///
+/// So every map can be written and parsed back, action names can't be empty,
+/// start with `#` or whitespace, end with whitespace or contain `:`, `|` or line
+/// breaks. Binding an action with a name like that fails with
+/// `Error::InvalidBinding`.
+///
+/// ``` rust
+/// use reminisce::{Axis, Button, Joystick, StatefulJoystick, VirtualJoystick};
+/// use reminisce::actions::{ActionMap, Binding};
+/// let mut actions = ActionMap::new()
+///     .with("jump", Binding::Button(Button::A))
+///     .with("fire", Binding::Axis(Axis::TriggerRight))
+///     .with("fire", Binding::Button(Button::RightShoulder))
+///     .with("move", Binding::AxisPair(Axis::LeftX, Axis::LeftY))
+///     .with("quit", Binding::Chord(vec![Button::Select, Button::Start]));
+/// let text = actions.to_string();
+/// assert_eq!(text, "jump: a\nfire: triggerright | rightshoulder\nmove: leftx,lefty\nquit: select+start\n");
+/// assert_eq!(text.parse::<ActionMap>().unwrap(), actions);
+///
+/// let js = VirtualJoystick::new(0, "Game Pad");
+/// let controller = js.controller();
+/// let mut js = js.with_state();
+/// controller.press(Button::RightShoulder);
+/// controller.press(Button::Start);
+/// controller.move_axis(Axis::LeftX, -32767);
+/// js.update();
+/// assert!(actions.is_active(&js, "fire"));
+/// assert!(!actions.is_active(&js, "jump") && !actions.is_active(&js, "quit"));
+/// assert_eq!(actions.get_vector(&js, "move"), (-1.0, 0.0));
+/// actions.rebind("jump", Binding::Button(Button::Start)).unwrap();
+/// assert!(actions.is_active(&js, "jump"));
+/// assert!(actions.bind("jump: again", Binding::Button(Button::B)).is_err());
+/// assert!(actions.bind("jump", Binding::Chord(Vec::new())).is_err());
+/// actions.rebind("quit", Binding::Chord(vec![Button::Start, Button::Start])).unwrap();
+/// assert_eq!(actions.get_bindings("quit"), &[Binding::Button(Button::Start)]);
+/// ```
+pub struct ActionMap {
+	actions: Vec<(String, Vec<Binding>)>
+}
+
+impl ActionMap {
+	/// Make a map with no actions
+	pub fn new() -> ActionMap {
+		ActionMap {
+			actions: Vec::new()
+		}
+	}
+
+	/// Read and parse the map in a file
+	pub fn load<P>(path: P) -> Result<ActionMap, Error> where P:AsRef<Path> {
+		use std::fs::File;
+		use std::io::Read;
+		let mut text = String::new();
+		try!(try!(File::open(path)).read_to_string(&mut text));
+		text.parse()
+	}
+
+	/// Write the map to a file, replacing it if it exists
+	///
+	/// ``` rust
+	/// use std::env;
+	/// use reminisce::Button;
+	/// use reminisce::actions::{ActionMap, Binding};
+	/// let actions = ActionMap::new().with("jump", Binding::Button(Button::A));
+	/// let path = env::temp_dir().join("reminisce-actions.txt");
+	/// actions.save(&path).unwrap();
+	/// assert_eq!(ActionMap::load(&path).unwrap(), actions);
+	/// ```
+	pub fn save<P>(&self, path: P) -> Result<(), Error> where P:AsRef<Path> {
+		use std::fs::File;
+		use std::io::Write;
+		try!(write!(try!(File::create(path)), "{}", self));
+		Ok(())
+	}
+
+	/// Get the position of an action
+	fn position(&self, action: &str) -> Option<usize> {
+		self.actions.iter().position(|&(ref name, _)| name == action)
+	}
+
+	/// Get the bindings of an action, adding it if it isn't in the map
+	fn entry(&mut self, action: &str) -> &mut Vec<Binding> {
+		let index = match self.position(action) {
+			Some(index) => index,
+			None => {
+				self.actions.push((action.to_string(), Vec::new()));
+				self.actions.len() - 1
+			}
+		};
+		&mut self.actions[index].1
+	}
+
+	/// Bind an action to an input, as well as the inputs it is already bound to
+	///
+	/// # Panics
+	///
+	/// This panics if the name of the action can't be used, or the binding is a
+	/// chord without any buttons.
+	pub fn with(mut self, action: &str, binding: Binding) -> ActionMap {
+		if let Err(err) = self.bind(action, binding) {
+			panic!("{}", err)
+		}
+		self
+	}
+
+	/// Bind an action to an input, as well as the inputs it is already bound to
+	pub fn bind(&mut self, action: &str, binding: Binding) -> Result<(), Error> {
+		try!(check_name(action));
+		let binding = try!(binding.normalise());
+		let bindings = self.entry(action);
+		if !bindings.contains(&binding) {
+			bindings.push(binding)
+		}
+		Ok(())
+	}
+
+	/// Bind an action to only one input
+	pub fn rebind(&mut self, action: &str, binding: Binding) -> Result<(), Error> {
+		try!(check_name(action));
+		let binding = try!(binding.normalise());
+		*self.entry(action) = vec![binding];
+		Ok(())
+	}
+
+	/// Unbind an action from every input, keeping it in the map
+	pub fn unbind(&mut self, action: &str) {
+		if let Some(index) = self.position(action) {
+			self.actions[index].1.clear()
+		}
+	}
+
+	/// Remove an action from the map
+	pub fn remove(&mut self, action: &str) {
+		if let Some(index) = self.position(action) {
+			self.actions.remove(index);
+		}
+	}
+
+	/// Get the names of the actions in the order they were added
+	pub fn actions(&self) -> Vec<&str> {
+		self.actions.iter().map(|&(ref name, _)| &name[..]).collect()
+	}
+
+	/// Get the inputs an action is bound to
+	pub fn get_bindings(&self, action: &str) -> &[Binding] {
+		match self.position(action) {
+			Some(index) => &self.actions[index].1,
+			None => &[]
+		}
+	}
+
+	/// Get the names of the actions bound to an input, to find conflicts when rebinding
+	///
+	/// ``` rust
+	/// use reminisce::Button;
+	/// use reminisce::actions::{ActionMap, Binding};
+	/// let actions = ActionMap::new().with("dodge", Binding::Chord(vec![Button::LeftShoulder, Button::A]));
+	/// assert_eq!(actions.get_actions(&Binding::Chord(vec![Button::A, Button::LeftShoulder])), vec!["dodge"]);
+	/// ```
+	pub fn get_actions(&self, binding: &Binding) -> Vec<&str> {
+		let binding = match binding.clone().normalise() {
+			Ok(binding) => binding,
+			Err(_) => return Vec::new()
+		};
+		self.actions.iter()
+			.filter(|&&(_, ref bindings)| bindings.contains(&binding))
+			.map(|&(ref name, _)| &name[..])
+			.collect()
+	}
+
+	/// Check if any input an action is bound to is active on a joystick
+	pub fn is_active<J>(&self, joystick: &J, action: &str) -> bool where J:StatefulJoystick {
+		self.get_bindings(action).iter().any(|binding| binding.is_active(joystick))
+	}
+
+	/// Get the value of the input an action is bound to which is pushed the furthest
+	///
+	/// This is zero if the action isn't bound to anything.
+	pub fn get_value<J>(&self, joystick: &J, action: &str) -> f32 where J:StatefulJoystick {
+		self.get_bindings(action).iter()
+			.map(|binding| binding.get_value(joystick))
+			.fold(0.0, |value, next| if next.abs() > value.abs() { next } else { value })
+	}
+
+	/// Get the vector of the input an action is bound to which is pushed the furthest
+	pub fn get_vector<J>(&self, joystick: &J, action: &str) -> (f32, f32) where J:StatefulJoystick {
+		self.get_bindings(action).iter()
+			.map(|binding| binding.get_vector(joystick))
+			.fold((0.0, 0.0), |(x, y), (next_x, next_y)| {
+				if next_x * next_x + next_y * next_y > x * x + y * y { (next_x, next_y) } else { (x, y) }
+			})
+	}
+}
+
+impl FromStr for ActionMap {
+	type Err = Error;
+	fn from_str(text: &str) -> Result<ActionMap, Error> {
+		let mut map = ActionMap::new();
+		for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+			let (name, bindings) = match line.find(':') {
+				Some(colon) => (line[..colon].trim(), &line[colon + 1..]),
+				None => return Err(Error::InvalidBinding(format!("no action name in {}", line)))
+			};
+			try!(check_name(name));
+			map.entry(name);
+			for binding in bindings.split('|').filter(|binding| !binding.trim().is_empty()) {
+				try!(map.bind(name, try!(binding.parse())));
+			}
+		}
+		Ok(map)
+	}
+}
+
+impl fmt::Display for ActionMap {
+	/// This writes every action on its own line
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for &(ref name, ref bindings) in self.actions.iter() {
+			try!(write!(fmt, "{}:", name));
+			for (index, binding) in bindings.iter().enumerate() {
+				try!(write!(fmt, "{}{}", if index == 0 { " " } else { " | " }, binding));
+			}
+			try!(writeln!(fmt, ""));
+		}
+		Ok(())
+	}
+}
+
+/// Check that an action name can be written in the text form of a map and
+/// parsed back
+fn check_name(name: &str) -> Result<(), Error> {
+	let valid = !name.is_empty() && name.trim() == name && !name.starts_with('#')
+		&& !name.contains(|c| c == ':' || c == '|' || c == '\n' || c == '\r');
+	if valid {
+		Ok(())
+	} else {
+		Err(Error::InvalidBinding(format!("{:?} can't be used as an action name", name)))
+	}
+}
+
+/// Listens to the events of a joystick for the next input the player uses, to
+/// rebind an action to it
+///
+/// Buttons are picked when they are released, so holding buttons down together
+/// picks them as a chord. Axes are picked once they are pushed past the
+/// `THRESHOLD`, unless a button is being held.
+///
+/// ``` rust
+/// use reminisce::{Axis, Button, Joystick, VirtualJoystick};
+/// use reminisce::actions::{ActionMap, Binding, BindingListener};
+/// let mut js = VirtualJoystick::new(0, "Game Pad");
+/// let controller = js.controller();
+/// let mut actions = ActionMap::new().with("reload", Binding::Button(Button::X));
+/// let mut listener = BindingListener::new();
+/// assert_eq!(listener.listen(&mut js), None);
+/// controller.press(Button::LeftShoulder);
+/// controller.press(Button::Y);
+/// assert_eq!(listener.listen(&mut js), None);
+/// controller.release(Button::Y);
+/// let binding = listener.listen(&mut js).unwrap();
+/// assert_eq!(binding, Binding::Chord(vec![Button::Y, Button::LeftShoulder]));
+/// actions.rebind("reload", binding).unwrap();
+/// assert_eq!(actions.to_string(), "reload: y+leftshoulder\n");
+///
+/// let mut listener = BindingListener::for_vectors();
+/// controller.move_axis(Axis::RightY, 2000);
+/// controller.move_axis(Axis::RightY, -30000);
+/// assert_eq!(listener.listen(&mut js), Some(Binding::AxisPair(Axis::RightX, Axis::RightY)));
+/// ```
+pub struct BindingListener {
+	chord: Vec<Button>,
+	pairs: bool
+}
+
+impl BindingListener {
+	/// Make a listener that picks buttons, chords and single axes
+	pub fn new() -> BindingListener {
+		BindingListener {
+			chord: Vec::new(),
+			pairs: false
+		}
+	}
+
+	/// Make a listener that picks both axes of a stick when either of them is pushed,
+	/// for actions that are vectors
+	pub fn for_vectors() -> BindingListener {
+		BindingListener {
+			chord: Vec::new(),
+			pairs: true
+		}
+	}
+
+	/// Forget the buttons that have been pressed so far
+	pub fn reset(&mut self) {
+		self.chord.clear()
+	}
+
+	/// Handle an event, and return the input it picks if it finishes one
+	pub fn feed(&mut self, event: Event) -> Option<Binding> {
+		match event {
+			Event::ButtonPressed(button) => {
+				if !self.chord.contains(&button) {
+					self.chord.push(button)
+				}
+				None
+			},
+			// Buttons that were already held when listening started are ignored
+			Event::ButtonReleased(button) if self.chord.contains(&button) => {
+				let binding = Binding::Chord(self.chord.clone()).normalise();
+				self.chord.clear();
+				binding.ok()
+			},
+			Event::AxisMoved(axis, value) if self.chord.is_empty() && axis_fraction(axis, value).abs() > THRESHOLD => {
+				match stick_pair(axis) {
+					Some((x, y)) if self.pairs => Some(Binding::AxisPair(x, y)),
+					_ => Some(Binding::Axis(axis))
+				}
+			},
+			_ => None
+		}
+	}
+
+	/// Poll a joystick until an input is picked, without blocking
+	///
+	/// This returns none if the joystick runs out of events first. Events after the
+	/// one that picks the input are left for the joystick to emit later.
+	pub fn listen<J>(&mut self, joystick: &mut J) -> Option<Binding> where J:Joystick {
+		while let Some(event) = joystick.poll() {
+			if let Some(binding) = self.feed(event) {
+				return Some(binding)
+			}
+		}
+		None
+	}
+}
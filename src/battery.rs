@@ -0,0 +1,158 @@
+#[cfg(target_os = "linux")]
+use std::fs::{self, File};
+#[cfg(target_os = "linux")]
+use std::io::Read;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// What the battery of a joystick is doing
+pub enum BatteryState {
+	/// The joystick is running off its battery
+	Discharging,
+	/// The joystick is plugged in and its battery is charging
+	Charging,
+	/// The joystick is plugged in and its battery is full
+	Full,
+	/// The joystick is plugged in and has no battery
+	Wired
+}
+
+impl BatteryState {
+	/// Get the state from the `status` of a Linux power supply
+	///
+	/// "Not charging" is reported by some controllers that are plugged in but have
+	/// stopped charging, which is treated as full.
+	///
+	/// ``` rust
+	/// use reminisce::BatteryState;
+	/// assert_eq!(BatteryState::from_status("Charging"), Some(BatteryState::Charging));
+	/// assert_eq!(BatteryState::from_status("Not charging"), Some(BatteryState::Full));
+	/// assert_eq!(BatteryState::from_status("Unknown"), None);
+	/// ```
+	pub fn from_status(status: &str) -> Option<BatteryState> {
+		match status.trim() {
+			"Discharging" => Some(BatteryState::Discharging),
+			"Charging" => Some(BatteryState::Charging),
+			"Full" | "Not charging" => Some(BatteryState::Full),
+			_ => None
+		}
+	}
+}
+
+/// Get the level of a battery from the `capacity_level` of a Linux power supply
+///
+/// This is used when the power supply doesn't report its `capacity` as a percentage.
+pub fn level_from_capacity_level(level: &str) -> Option<f32> {
+	match level.trim() {
+		"Critical" => Some(0.05),
+		"Low" => Some(0.25),
+		"Normal" => Some(0.5),
+		"High" => Some(0.75),
+		"Full" => Some(1.0),
+		_ => None
+	}
+}
+
+#[cfg(target_os = "linux")]
+/// The battery of a device, as found in `/sys/class/power_supply`
+///
+/// ``` rust
+/// use std::fs;
+/// use reminisce::BatteryState;
+/// use reminisce::battery::PowerSupply;
+/// let dir = std::env::temp_dir().join("reminisce-battery-doc");
+/// let supply = dir.join("device").join("power_supply").join("sony_controller_battery_00:11:22:33:44:55");
+/// fs::create_dir_all(&supply).unwrap();
+/// fs::write(supply.join("capacity"), "75\n").unwrap();
+/// fs::write(supply.join("status"), "Discharging\n").unwrap();
+/// let supply = PowerSupply::find(&dir).unwrap();
+/// assert_eq!(supply.get_level(), Some(0.75));
+/// assert_eq!(supply.get_state(), Some(BatteryState::Discharging));
+/// // An input device under the device with the battery
+/// let input = dir.join("device").join("input").join("input5");
+/// fs::create_dir_all(&input).unwrap();
+/// assert_eq!(PowerSupply::find(&input).unwrap().get_level(), Some(0.75));
+/// // An input device without a battery
+/// let other = std::env::temp_dir().join("reminisce-battery-doc-wired");
+/// fs::create_dir_all(&other).unwrap();
+/// assert!(PowerSupply::find(&other).is_none());
+/// fs::remove_dir_all(&dir).unwrap();
+/// fs::remove_dir_all(&other).unwrap();
+/// ```
+pub struct PowerSupply {
+	path: PathBuf
+}
+
+#[cfg(target_os = "linux")]
+impl PowerSupply {
+	/// Find the power supply of an input device from its directory in sysfs, like
+	/// `/sys/class/input/js0/device`
+	///
+	/// The power supply is in the `power_supply` directory of the HID device the input
+	/// device belongs to, which is its parent or one of the devices above it. Only
+	/// the devices the input device is under are searched, so the batteries of other
+	/// devices, like the laptop it is plugged into, are never returned.
+	pub fn find<P>(device: P) -> Option<PowerSupply> where P:AsRef<Path> {
+		let device = device.as_ref();
+		if let Some(path) = PowerSupply::first_entry(&device.join("device").join("power_supply")) {
+			return Some(PowerSupply { path: path })
+		}
+		let device = match device.canonicalize() {
+			Ok(device) => device,
+			Err(_) => return None
+		};
+		let above = device.ancestors().take_while(|dir| *dir != Path::new("/sys/devices"));
+		above.filter_map(|dir| PowerSupply::first_entry(&dir.join("power_supply"))).next().map(|path| PowerSupply { path: path })
+	}
+
+	/// Get the first entry in a directory, if it exists and has one
+	fn first_entry(dir: &Path) -> Option<PathBuf> {
+		fs::read_dir(dir).ok().and_then(|mut entries| entries.next()).and_then(|entry| entry.ok()).map(|entry| entry.path())
+	}
+
+	/// Get the path to the power supply in sysfs
+	pub fn get_path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Get the level of the battery between 0 and 1
+	///
+	/// This reads `capacity`, or `capacity_level` if that isn't there.
+	pub fn get_level(&self) -> Option<f32> {
+		match read(&self.path.join("capacity")).and_then(|text| text.parse::<u8>().ok()) {
+			Some(percent) => Some(percent.min(100) as f32 / 100.0),
+			None => read(&self.path.join("capacity_level")).and_then(|text| level_from_capacity_level(&text))
+		}
+	}
+
+	/// Get what the battery is doing from its `status`
+	pub fn get_state(&self) -> Option<BatteryState> {
+		read(&self.path.join("status")).and_then(|text| BatteryState::from_status(&text))
+	}
+}
+
+#[cfg(target_os = "linux")]
+/// Read the trimmed contents of a file in sysfs
+fn read(path: &Path) -> Option<String> {
+	let mut text = String::new();
+	match File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+		Ok(_) => Some(text.trim().to_string()),
+		Err(_) => None
+	}
+}
+
+#[cfg(target_os = "linux")]
+/// Get the state of the battery of an input device from its directory in sysfs
+///
+/// USB devices without a power supply are reported as `BatteryState::Wired`.
+pub fn read_state<P>(device: P) -> Option<BatteryState> where P:AsRef<Path> {
+	let device = device.as_ref();
+	match PowerSupply::find(device) {
+		Some(supply) => supply.get_state(),
+		None => match read(&device.join("id").join("bustype")).and_then(|text| u16::from_str_radix(&text, 16).ok()) {
+			Some(::BUS_USB) => Some(BatteryState::Wired),
+			_ => None
+		}
+	}
+}
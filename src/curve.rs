@@ -0,0 +1,463 @@
+use {Axis, Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent};
+use {MAX_AXIS_VALUE, MIN_AXIS_VALUE, NUM_AXES};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How many times the parameter of a bezier curve is halved while solving for it
+static BEZIER_STEPS: usize = 24;
+
+#[derive(Clone, Debug, PartialEq)]
+/// A response curve, which changes how far an axis reports it is pushed
+///
+/// Curves map how far an axis is pushed, as a fraction between 0 and 1, to how
+/// far it is reported as pushed. Sticks keep their direction, so the curve is
+/// applied to how far they are from the centre. Triggers are assumed to rest at
+/// `MIN_AXIS_VALUE`, like they do on Linux.
+///
+/// Curves can be written as text and parsed back:
+///
+/// ``` rust
+/// use reminisce::Curve;
+/// let curves = vec![
+///     Curve::Linear,
+///     Curve::Power(2.5),
+///     Curve::Exponential(3.0),
+///     Curve::Bezier(0.4, 0.0, 0.8, 0.6),
+///     Curve::Table(vec![(0.0, 0.0), (0.5, 0.2), (1.0, 1.0)])
+/// ];
+/// for curve in curves {
+///     assert_eq!(curve.to_string().parse::<Curve>().unwrap(), curve);
+/// }
+/// assert_eq!(Curve::Power(2.0).to_string(), "power(2)");
+/// assert!(("table(0:0, 0.5:0.2, 1:1)".parse::<Curve>().unwrap().apply_fraction(0.75) - 0.6).abs() < 1e-6);
+/// assert!("power(0)".parse::<Curve>().is_err());
+/// assert!(Curve::power(-1.0).is_err());
+/// // A power that isn't above 0 is treated as linear
+/// assert_eq!(Curve::Power(0.0).apply_fraction(0.0), 0.0);
+/// assert!("exponential(inf)".parse::<Curve>().is_err());
+/// // Steep curves stay between 0 and 1 instead of overflowing
+/// let steep = Curve::Exponential(1000.0);
+/// assert_eq!((steep.apply_fraction(0.0), steep.apply_fraction(0.5), steep.apply_fraction(1.0)), (0.0, 0.0, 1.0));
+/// ```
+pub enum Curve {
+	/// The axis is reported as it is
+	Linear,
+	/// The fraction is raised to a power, so powers above 1 give more precision
+	/// near the centre
+	///
+	/// The power must be above 0, because other powers don't map 0 to 0 and 1 to 1.
+	/// `Curve::power` checks this, and other powers are applied as `Linear`.
+	Power(f32),
+	/// The fraction is scaled exponentially by a factor, so factors above 0 give
+	/// more precision near the centre and factors below 0 give less
+	///
+	/// This is `(e^(k * x) - 1) / (e^k - 1)` for a factor `k`, which is worked out
+	/// so it stays accurate for any factor however large.
+	Exponential(f32),
+	/// A cubic bezier curve from (0, 0) to (1, 1) with two control points, given
+	/// as `x1, y1, x2, y2` like the `cubic-bezier` easing function in CSS
+	///
+	/// The x coordinates of the control points are clamped between 0 and 1 so
+	/// there is only one value for every fraction.
+	Bezier(f32, f32, f32, f32),
+	/// A lookup table of points, which are joined by straight lines
+	///
+	/// The points must be in order of their x coordinates. Fractions before the
+	/// first point or after the last one are reported as the value of that point.
+	Table(Vec<(f32, f32)>)
+}
+
+impl Default for Curve {
+	fn default() -> Curve {
+		Curve::Linear
+	}
+}
+
+/// Get a coordinate of a cubic bezier curve from (0, 0) to (1, 1) at a parameter
+fn bezier(t: f32, p1: f32, p2: f32) -> f32 {
+	let u = 1.0 - t;
+	3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+}
+
+impl Curve {
+	/// Make a power curve, checking that the power is above 0
+	pub fn power(power: f32) -> Result<Curve, Error> {
+		if power > 0.0 && power.is_finite() {
+			Ok(Curve::Power(power))
+		} else {
+			Err(Error::InvalidCurve(format!("the power {} is not above 0", power)))
+		}
+	}
+
+	/// Apply this curve to a fraction between 0 and 1
+	pub fn apply_fraction(&self, x: f32) -> f32 {
+		let x = x.max(0.0).min(1.0);
+		match *self {
+			Curve::Linear => x,
+			Curve::Power(power) if power > 0.0 && power.is_finite() => x.powf(power),
+			Curve::Power(_) => x,
+			Curve::Exponential(factor) if factor == 0.0 => x,
+			Curve::Exponential(factor) if factor > 0.0 => {
+				// Dividing through by e^k keeps every term between -1 and 0, so large
+				// factors can't overflow to infinity
+				let shrink = (-factor).exp_m1();
+				(shrink - (factor * (x - 1.0)).exp_m1()) / shrink
+			},
+			Curve::Exponential(factor) => (factor * x).exp_m1() / factor.exp_m1(),
+			Curve::Bezier(x1, y1, x2, y2) => {
+				let (x1, x2) = (x1.max(0.0).min(1.0), x2.max(0.0).min(1.0));
+				// The x coordinate only increases along the curve, so the parameter
+				// can be found by bisection
+				let (mut low, mut high) = (0.0, 1.0);
+				for _ in 0..BEZIER_STEPS {
+					let mid = (low + high) / 2.0;
+					if bezier(mid, x1, x2) < x {
+						low = mid
+					} else {
+						high = mid
+					}
+				}
+				bezier((low + high) / 2.0, y1, y2)
+			},
+			Curve::Table(ref points) => {
+				let (first, last) = match (points.first(), points.last()) {
+					(Some(&first), Some(&last)) => (first, last),
+					_ => return x
+				};
+				if x <= first.0 {
+					return first.1
+				}
+				for pair in points.windows(2) {
+					let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+					if x <= x2 {
+						return if x2 <= x1 { y2 } else { y1 + (x - x1) / (x2 - x1) * (y2 - y1) }
+					}
+				}
+				last.1
+			}
+		}
+	}
+
+	/// Apply this curve to the value of a stick axis, keeping its direction
+	///
+	/// ``` rust
+	/// use reminisce::Curve;
+	/// let curve = Curve::Table(vec![(0.0, 0.0), (0.5, 0.25), (1.0, 1.0)]);
+	/// assert_eq!(curve.apply_stick(0), 0);
+	/// assert_eq!(curve.apply_stick(-32767), -32767);
+	/// assert_eq!(curve.apply_stick(16000), 8000);
+	/// assert_eq!(Curve::Linear.apply_stick(-1234), -1234);
+	/// ```
+	pub fn apply_stick(&self, value: i16) -> i16 {
+		let fraction = (value as f32 / MAX_AXIS_VALUE as f32).max(-1.0).min(1.0);
+		let curved = self.apply_fraction(fraction.abs()).max(0.0).min(1.0);
+		(curved * fraction.signum() * MAX_AXIS_VALUE as f32).round() as i16
+	}
+
+	/// Apply this curve to the value of a trigger, which rests at `MIN_AXIS_VALUE`
+	///
+	/// ``` rust
+	/// use reminisce::{Curve, MIN_AXIS_VALUE, MAX_AXIS_VALUE};
+	/// let curve = Curve::Power(2.0);
+	/// assert_eq!(curve.apply_trigger(MIN_AXIS_VALUE), MIN_AXIS_VALUE);
+	/// assert_eq!(curve.apply_trigger(0), -16384);
+	/// assert_eq!(curve.apply_trigger(MAX_AXIS_VALUE), MAX_AXIS_VALUE);
+	/// ```
+	pub fn apply_trigger(&self, value: i16) -> i16 {
+		let range = MAX_AXIS_VALUE as f32 - MIN_AXIS_VALUE as f32;
+		let position = (value as f32 - MIN_AXIS_VALUE as f32) / range;
+		let curved = self.apply_fraction(position).max(0.0).min(1.0);
+		(MIN_AXIS_VALUE as f32 + curved * range).round() as i16
+	}
+
+	/// Apply this curve to the value of an axis
+	pub fn apply(&self, axis: Axis, value: i16) -> i16 {
+		match axis {
+			Axis::TriggerLeft | Axis::TriggerRight => self.apply_trigger(value),
+			_ => self.apply_stick(value)
+		}
+	}
+}
+
+/// Parse a number in a curve, which must be finite
+fn parse_number(text: &str) -> Result<f32, Error> {
+	match text.trim().parse::<f32>() {
+		Ok(number) if number.is_finite() => Ok(number),
+		_ => Err(Error::InvalidCurve(format!("{} is not a number", text.trim())))
+	}
+}
+
+impl FromStr for Curve {
+	type Err = Error;
+	fn from_str(text: &str) -> Result<Curve, Error> {
+		let text = text.trim();
+		if text == "linear" {
+			return Ok(Curve::Linear)
+		}
+		let (name, args) = match (text.find('('), text.ends_with(')')) {
+			(Some(open), true) => (text[..open].trim(), &text[open + 1..text.len() - 1]),
+			_ => return Err(Error::InvalidCurve(format!("no arguments in {}", text)))
+		};
+		if name == "table" {
+			let mut points = Vec::new();
+			for point in args.split(',').filter(|point| !point.trim().is_empty()) {
+				let point = match point.find(':') {
+					Some(colon) => (try!(parse_number(&point[..colon])), try!(parse_number(&point[colon + 1..]))),
+					None => return Err(Error::InvalidCurve(format!("no value for {} in {}", point.trim(), text)))
+				};
+				if points.last().map(|&(x, _)| point.0 < x).unwrap_or(false) {
+					return Err(Error::InvalidCurve(format!("points out of order in {}", text)))
+				}
+				points.push(point);
+			}
+			return Ok(Curve::Table(points))
+		}
+		let mut numbers = Vec::with_capacity(4);
+		for arg in args.split(',') {
+			numbers.push(try!(parse_number(arg)));
+		}
+		match (name, &numbers[..]) {
+			("power", &[power]) => Curve::power(power),
+			("exponential", &[factor]) => Ok(Curve::Exponential(factor)),
+			("bezier", &[x1, y1, x2, y2]) => Ok(Curve::Bezier(x1, y1, x2, y2)),
+			("power", _) | ("exponential", _) | ("bezier", _) =>
+				Err(Error::InvalidCurve(format!("wrong number of arguments in {}", text))),
+			_ => Err(Error::InvalidCurve(format!("unknown curve {}", name)))
+		}
+	}
+}
+
+impl fmt::Display for Curve {
+	/// This writes the curve as its name followed by its arguments in brackets,
+	/// like `power(2.5)` or `table(0:0,0.5:0.2,1:1)`
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Curve::Linear => write!(fmt, "linear"),
+			Curve::Power(power) => write!(fmt, "power({})", power),
+			Curve::Exponential(factor) => write!(fmt, "exponential({})", factor),
+			Curve::Bezier(x1, y1, x2, y2) => write!(fmt, "bezier({},{},{},{})", x1, y1, x2, y2),
+			Curve::Table(ref points) => {
+				try!(write!(fmt, "table("));
+				for (index, &(x, y)) in points.iter().enumerate() {
+					try!(write!(fmt, "{}{}:{}", if index == 0 { "" } else { "," }, x, y));
+				}
+				write!(fmt, ")")
+			}
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The response curve of every `Axis` of a joystick, which are linear by default
+///
+/// A profile is written as text with one line for every axis that isn't linear,
+/// and can be parsed back. Blank lines and lines starting with `#` are skipped
+/// when parsing.
+///
+/// ``` rust
+/// use reminisce::{Axis, Curve, Curves};
+/// let aiming = Curves::new()
+///     .with(Axis::RightX, Curve::Exponential(2.0))
+///     .with(Axis::RightY, Curve::Exponential(2.0));
+/// let text = aiming.to_string();
+/// assert_eq!(text, "rightx: exponential(2)\nrighty: exponential(2)\n");
+/// assert_eq!(text.parse::<Curves>().unwrap(), aiming);
+/// let menus: Curves = "# Menus\nlefty: linear\n".parse().unwrap();
+/// assert_eq!(menus, Curves::new());
+/// ```
+pub struct Curves {
+	curves: Vec<Curve>
+}
+
+impl Default for Curves {
+	fn default() -> Curves {
+		Curves::new()
+	}
+}
+
+impl Curves {
+	/// Make a profile where every axis is linear
+	pub fn new() -> Curves {
+		Curves {
+			curves: vec![Curve::Linear; NUM_AXES as usize]
+		}
+	}
+
+	/// Make a profile where every axis uses the same curve
+	pub fn all(curve: Curve) -> Curves {
+		Curves {
+			curves: vec![curve; NUM_AXES as usize]
+		}
+	}
+
+	/// Set the curve of an axis
+	pub fn with(mut self, axis: Axis, curve: Curve) -> Curves {
+		self.set(axis, curve);
+		self
+	}
+
+	/// Get the curve of an axis
+	pub fn get(&self, axis: Axis) -> &Curve {
+		&self.curves[axis as usize]
+	}
+
+	/// Set the curve of an axis
+	pub fn set(&mut self, axis: Axis, curve: Curve) {
+		self.curves[axis as usize] = curve
+	}
+
+	/// Apply the curve of an axis to its value
+	pub fn apply(&self, axis: Axis, value: i16) -> i16 {
+		self.get(axis).apply(axis, value)
+	}
+}
+
+impl FromStr for Curves {
+	type Err = Error;
+	fn from_str(text: &str) -> Result<Curves, Error> {
+		let mut curves = Curves::new();
+		for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+			let colon = match line.find(':') {
+				Some(colon) => colon,
+				None => return Err(Error::InvalidCurve(format!("no curve for {}", line)))
+			};
+			let axis = try!(line[..colon].trim().parse().map_err(Error::InvalidCurve));
+			curves.set(axis, try!(line[colon + 1..].parse()));
+		}
+		Ok(curves)
+	}
+}
+
+impl fmt::Display for Curves {
+	/// This writes every axis that isn't linear on its own line
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for index in 0..NUM_AXES {
+			let axis = Axis::from_index(index).unwrap();
+			let curve = self.get(axis);
+			if *curve != Curve::Linear {
+				try!(writeln!(fmt, "{}: {}", axis, curve));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A joystick with response curves applied to its axes
+///
+/// The curves are applied to the axis events it emits and, if the joystick it
+/// wraps is stateful, to the state of its axes. Raw axes are left as they are.
+/// This can wrap a `JoystickMapper` to apply the curves to the axes after they
+/// are mapped.
+///
+/// ``` rust
+/// use reminisce::{Axis, Curve, Curves, Event, Joystick, StatefulJoystick, VirtualJoystick};
+/// let js = VirtualJoystick::new(0, "Aiming Pad");
+/// let controller = js.controller();
+/// let mut js = js.with_curves(Curves::new().with(Axis::RightX, Curve::Power(2.0)));
+/// controller.move_axis(Axis::RightX, -32767);
+/// controller.move_axis(Axis::LeftX, 16384);
+/// assert_eq!(js.poll(), Some(Event::AxisMoved(Axis::RightX, -32767)));
+/// assert_eq!(js.poll(), Some(Event::AxisMoved(Axis::LeftX, 16384)));
+/// controller.move_axis(Axis::RightX, 16384);
+/// assert_eq!(js.poll(), Some(Event::AxisMoved(Axis::RightX, 8192)));
+/// assert_eq!(js.get_axis(Axis::RightX), Some(8192));
+/// ```
+pub struct CurveJoystick<J> where J:Joystick {
+	joystick: J,
+	curves: Curves
+}
+
+impl<J> CurveJoystick<J> where J:Joystick {
+	/// Start applying response curves to a joystick
+	pub fn new(joystick: J, curves: Curves) -> CurveJoystick<J> {
+		CurveJoystick {
+			joystick: joystick,
+			curves: curves
+		}
+	}
+
+	/// Get the response curves
+	pub fn get_curves(&self) -> &Curves {
+		&self.curves
+	}
+
+	/// Change the response curves
+	pub fn set_curves(&mut self, curves: Curves) {
+		self.curves = curves
+	}
+
+	/// Get the joystick this wraps
+	pub fn get_ref(&self) -> &J {
+		&self.joystick
+	}
+
+	/// Get the joystick this wraps mutably
+	pub fn get_mut(&mut self) -> &mut J {
+		&mut self.joystick
+	}
+
+	/// Unwrap the joystick
+	pub fn into_inner(self) -> J {
+		self.joystick
+	}
+}
+
+impl<J> Joystick for CurveJoystick<J> where J:Joystick {
+	type WithState = CurveJoystick<<J as Joystick>::WithState>;
+	type NativeEvent = <J as Joystick>::NativeEvent;
+
+	fn open(index: u8) -> Result<CurveJoystick<J>, Error> {
+		Ok(CurveJoystick::new(try!(Joystick::open(index)), Curves::new()))
+	}
+	delegate_joystick!(methods);
+	fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+		let mut timed = match try!(self.joystick.try_poll_timed()) {
+			Some(timed) => timed,
+			None => return Ok(None)
+		};
+		timed.event = match timed.event {
+			Event::AxisMoved(axis, value) => Event::AxisMoved(axis, self.curves.apply(axis, value)),
+			Event::InitialAxis(axis, value) => Event::InitialAxis(axis, self.curves.apply(axis, value)),
+			event => event
+		};
+		Ok(Some(timed))
+	}
+	delegate_joystick!(mappings);
+	fn with_state(self) -> CurveJoystick<<J as Joystick>::WithState> {
+		CurveJoystick {
+			joystick: self.joystick.with_state(),
+			curves: self.curves
+		}
+	}
+}
+
+delegate_joystick!(impls CurveJoystick, Joystick);
+
+impl<J> StatefulJoystick for CurveJoystick<J> where J:StatefulJoystick {
+	/// The curve of the axis is applied to its value
+	fn get_axis(&self, axis: Axis) -> Option<i16> {
+		self.joystick.get_axis(axis).map(|value| self.curves.apply(axis, value))
+	}
+	fn get_button(&self, button: Button) -> Option<bool> {
+		self.joystick.get_button(button)
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.joystick.get_raw_axis(index)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.joystick.get_raw_button(index)
+	}
+	fn get_axis_time(&self, axis: Axis) -> Option<Duration> {
+		self.joystick.get_axis_time(axis)
+	}
+	fn get_button_time(&self, button: Button) -> Option<Duration> {
+		self.joystick.get_button_time(button)
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.joystick.get_hat(index)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
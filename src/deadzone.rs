@@ -0,0 +1,323 @@
+use {Axis, Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent};
+use {MAX_AXIS_VALUE, MIN_AXIS_VALUE, NUM_AXES};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How the dead zone of a stick is shaped
+pub enum StickMode {
+	/// Each axis of the stick is zeroed on its own while it is inside the dead zone
+	///
+	/// This makes it easier to move in a straight line, but snaps diagonal movement
+	/// to the axes near the centre.
+	Axial,
+	/// The stick is zeroed while it is inside a circle around the centre
+	Radial,
+	/// The stick is zeroed while it is inside a circle around the centre, and the
+	/// rest of its range is scaled so it starts at zero at the edge of the circle
+	ScaledRadial
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The dead zones of the sticks and triggers of a joystick
+///
+/// Thresholds are fractions of the range of a stick or trigger. Values inside
+/// the inner threshold are reported as resting and values past the outer
+/// threshold are reported as all the way.
+///
+/// Triggers are assumed to rest at `MIN_AXIS_VALUE`, like they do on Linux.
+pub struct DeadZones {
+	/// How the dead zones of the sticks are shaped
+	pub mode: StickMode,
+	/// The inner threshold of the sticks
+	pub stick_inner: f32,
+	/// The outer threshold of the sticks
+	pub stick_outer: f32,
+	/// The inner threshold of the triggers
+	pub trigger_inner: f32,
+	/// The outer threshold of the triggers
+	pub trigger_outer: f32
+}
+
+impl Default for DeadZones {
+	/// A scaled radial dead zone of 15% for the sticks and 5% for the triggers
+	fn default() -> DeadZones {
+		DeadZones::new(StickMode::ScaledRadial, 0.15, 1.0).with_triggers(0.05, 1.0)
+	}
+}
+
+/// Convert an axis value into a fraction between -1 and 1
+fn fraction(value: i16) -> f32 {
+	(value as f32 / MAX_AXIS_VALUE as f32).max(-1.0).min(1.0)
+}
+
+/// Convert a fraction between -1 and 1 into an axis value
+fn value(fraction: f32) -> i16 {
+	(fraction.max(-1.0).min(1.0) * MAX_AXIS_VALUE as f32).round() as i16
+}
+
+/// Scale a magnitude so the inner threshold is 0 and the outer threshold is 1
+fn rescale(magnitude: f32, inner: f32, outer: f32) -> f32 {
+	if outer <= inner {
+		if magnitude < inner { 0.0 } else { 1.0 }
+	} else {
+		((magnitude - inner) / (outer - inner)).max(0.0).min(1.0)
+	}
+}
+
+/// Get the other axis of the stick an axis is part of, if it is part of one
+fn stick_pair(axis: Axis) -> Option<(Axis, Axis)> {
+	match axis {
+		Axis::LeftX | Axis::LeftY => Some((Axis::LeftX, Axis::LeftY)),
+		Axis::RightX | Axis::RightY => Some((Axis::RightX, Axis::RightY)),
+		Axis::TriggerLeft | Axis::TriggerRight => None
+	}
+}
+
+impl DeadZones {
+	/// Make dead zones for the sticks, with no dead zone for the triggers
+	pub fn new(mode: StickMode, inner: f32, outer: f32) -> DeadZones {
+		DeadZones {
+			mode: mode,
+			stick_inner: inner,
+			stick_outer: outer,
+			trigger_inner: 0.0,
+			trigger_outer: 1.0
+		}
+	}
+
+	/// Set the thresholds of the triggers
+	pub fn with_triggers(mut self, inner: f32, outer: f32) -> DeadZones {
+		self.trigger_inner = inner;
+		self.trigger_outer = outer;
+		self
+	}
+
+	/// Apply the dead zone of a stick to the values of its horizontal and vertical axes
+	///
+	/// ``` rust
+	/// use reminisce::{DeadZones, StickMode};
+	/// let axial = DeadZones::new(StickMode::Axial, 0.2, 1.0);
+	/// assert_eq!(axial.apply_stick(3000, 20000), (0, 20000));
+	/// let radial = DeadZones::new(StickMode::Radial, 0.2, 1.0);
+	/// assert_eq!(radial.apply_stick(3000, 3000), (0, 0));
+	/// assert_eq!(radial.apply_stick(3000, 20000), (3000, 20000));
+	/// let scaled = DeadZones::new(StickMode::ScaledRadial, 0.5, 1.0);
+	/// assert_eq!(scaled.apply_stick(0, 16000), (0, 0));
+	/// assert_eq!(scaled.apply_stick(0, -32767), (0, -32767));
+	/// assert_eq!(scaled.apply_stick(24575, 0), (16383, 0));
+	/// ```
+	pub fn apply_stick(&self, x: i16, y: i16) -> (i16, i16) {
+		let (x, y) = (fraction(x), fraction(y));
+		let (inner, outer) = (self.stick_inner, self.stick_outer);
+		let (x, y) = match self.mode {
+			StickMode::Axial => {
+				let axial = |v: f32| if v.abs() < inner {
+					0.0
+				} else if v.abs() >= outer {
+					v.signum()
+				} else {
+					v
+				};
+				(axial(x), axial(y))
+			},
+			StickMode::Radial | StickMode::ScaledRadial => {
+				let magnitude = (x * x + y * y).sqrt();
+				if magnitude < inner || magnitude == 0.0 {
+					(0.0, 0.0)
+				} else {
+					let scaled = match self.mode {
+						StickMode::ScaledRadial => rescale(magnitude, inner, outer),
+						_ if magnitude >= outer => 1.0,
+						_ => magnitude
+					};
+					(x / magnitude * scaled, y / magnitude * scaled)
+				}
+			}
+		};
+		(value(x), value(y))
+	}
+
+	/// Apply the dead zone of a trigger to its value
+	///
+	/// ``` rust
+	/// use reminisce::{DeadZones, StickMode, MIN_AXIS_VALUE, MAX_AXIS_VALUE};
+	/// let zones = DeadZones::new(StickMode::Axial, 0.0, 1.0).with_triggers(0.1, 0.9);
+	/// assert_eq!(zones.apply_trigger(-30000), MIN_AXIS_VALUE);
+	/// assert_eq!(zones.apply_trigger(0), 0);
+	/// assert_eq!(zones.apply_trigger(30000), MAX_AXIS_VALUE);
+	/// ```
+	pub fn apply_trigger(&self, value: i16) -> i16 {
+		let range = MAX_AXIS_VALUE as f32 - MIN_AXIS_VALUE as f32;
+		let position = (value as f32 - MIN_AXIS_VALUE as f32) / range;
+		let position = rescale(position, self.trigger_inner, self.trigger_outer);
+		(MIN_AXIS_VALUE as f32 + position * range).round() as i16
+	}
+
+	/// Apply the dead zones to the value of an axis, given the value of every axis
+	fn apply<F>(&self, axis: Axis, get_axis: F) -> Option<i16> where F:Fn(Axis) -> Option<i16> {
+		match stick_pair(axis) {
+			Some((x_axis, y_axis)) => {
+				let (x, y) = (get_axis(x_axis), get_axis(y_axis));
+				if x.is_none() && y.is_none() {
+					return None
+				}
+				let (x, y) = self.apply_stick(x.unwrap_or(0), y.unwrap_or(0));
+				Some(if axis == x_axis { x } else { y })
+			},
+			None => get_axis(axis).map(|value| self.apply_trigger(value))
+		}
+	}
+}
+
+/// A joystick with dead zones applied to its sticks and triggers
+///
+/// The dead zones are applied to the state of the axes and to the axis events it
+/// emits. Axis events that don't change the value after the dead zones are applied,
+/// like those from a stick drifting inside its dead zone, are suppressed. With
+/// radial dead zones, moving one axis of a stick can emit events for both of its axes.
+///
+/// ``` rust
+/// use reminisce::{Axis, DeadZones, Event, Joystick, StatefulJoystick, StickMode, VirtualJoystick};
+/// let js = VirtualJoystick::new(0, "Drifting Pad");
+/// let controller = js.controller();
+/// let mut js = js.with_dead_zones(DeadZones::new(StickMode::Radial, 0.2, 1.0));
+/// controller.move_axis(Axis::LeftX, 1000);
+/// assert_eq!(js.poll(), None);
+/// assert_eq!(js.get_axis(Axis::LeftX), Some(0));
+/// controller.move_axis(Axis::LeftX, 20000);
+/// assert_eq!(js.poll(), Some(Event::AxisMoved(Axis::LeftX, 20000)));
+/// ```
+pub struct DeadZoneJoystick<J> where J:StatefulJoystick {
+	joystick: J,
+	zones: DeadZones,
+	emitted: Vec<Option<i16>>,
+	pending: VecDeque<TimedEvent>
+}
+
+impl<J> DeadZoneJoystick<J> where J:StatefulJoystick {
+	/// Start applying dead zones to a joystick
+	pub fn new(joystick: J, zones: DeadZones) -> DeadZoneJoystick<J> {
+		// Events are only emitted once the axes move from where they are now
+		let emitted = (0..NUM_AXES).map(|index| {
+			let axis = Axis::from_index(index).unwrap();
+			zones.apply(axis, |axis| joystick.get_axis(axis))
+		}).collect();
+		DeadZoneJoystick {
+			joystick: joystick,
+			zones: zones,
+			emitted: emitted,
+			pending: VecDeque::with_capacity(2)
+		}
+	}
+
+	/// Get the dead zones
+	pub fn get_dead_zones(&self) -> DeadZones {
+		self.zones
+	}
+
+	/// Change the dead zones
+	pub fn set_dead_zones(&mut self, zones: DeadZones) {
+		self.zones = zones
+	}
+
+	/// Get the joystick this wraps
+	pub fn get_ref(&self) -> &J {
+		&self.joystick
+	}
+
+	/// Unwrap the joystick
+	pub fn into_inner(self) -> J {
+		self.joystick
+	}
+
+	/// Queue an axis event if the dead zones changed the value that was last emitted
+	fn queue(&mut self, axis: Axis, initial: bool, time: Duration, source: u8) {
+		let joystick = &self.joystick;
+		if let Some(value) = self.zones.apply(axis, |axis| joystick.get_axis(axis)) {
+			let emitted = &mut self.emitted[axis as usize];
+			if initial || *emitted != Some(value) {
+				*emitted = Some(value);
+				self.pending.push_back(TimedEvent {
+					event: if initial { Event::InitialAxis(axis, value) } else { Event::AxisMoved(axis, value) },
+					time: time,
+					source: source
+				});
+			}
+		}
+	}
+}
+
+impl<J> Joystick for DeadZoneJoystick<J> where J:StatefulJoystick {
+	type WithState = DeadZoneJoystick<<J as Joystick>::WithState>;
+	type NativeEvent = <J as Joystick>::NativeEvent;
+
+	fn open(index: u8) -> Result<DeadZoneJoystick<J>, Error> {
+		Ok(DeadZoneJoystick::new(try!(Joystick::open(index)), DeadZones::default()))
+	}
+	delegate_joystick!(methods);
+	fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+		loop {
+			if let Some(timed) = self.pending.pop_front() {
+				return Ok(Some(timed))
+			}
+			let timed = match try!(self.joystick.try_poll_timed()) {
+				Some(timed) => timed,
+				None => return Ok(None)
+			};
+			let (axis, initial) = match timed.event {
+				Event::AxisMoved(axis, _) => (axis, false),
+				Event::InitialAxis(axis, _) => (axis, true),
+				_ => return Ok(Some(timed))
+			};
+			match stick_pair(axis) {
+				Some((x_axis, y_axis)) => {
+					// Only the axis that moved reports its initial value
+					self.queue(x_axis, initial && axis == x_axis, timed.time, timed.source);
+					self.queue(y_axis, initial && axis == y_axis, timed.time, timed.source);
+				},
+				None => self.queue(axis, initial, timed.time, timed.source)
+			}
+		}
+	}
+	delegate_joystick!(mappings);
+	fn with_state(self) -> DeadZoneJoystick<<J as Joystick>::WithState> {
+		DeadZoneJoystick {
+			joystick: self.joystick.with_state(),
+			zones: self.zones,
+			emitted: self.emitted,
+			pending: self.pending
+		}
+	}
+}
+
+delegate_joystick!(impls DeadZoneJoystick, StatefulJoystick);
+
+impl<J> StatefulJoystick for DeadZoneJoystick<J> where J:StatefulJoystick {
+	/// The dead zones are applied to the value of the axis
+	fn get_axis(&self, axis: Axis) -> Option<i16> {
+		let joystick = &self.joystick;
+		self.zones.apply(axis, |axis| joystick.get_axis(axis))
+	}
+	fn get_button(&self, button: Button) -> Option<bool> {
+		self.joystick.get_button(button)
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.joystick.get_raw_axis(index)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.joystick.get_raw_button(index)
+	}
+	fn get_axis_time(&self, axis: Axis) -> Option<Duration> {
+		self.joystick.get_axis_time(axis)
+	}
+	fn get_button_time(&self, button: Button) -> Option<Duration> {
+		self.joystick.get_button_time(button)
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.joystick.get_hat(index)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
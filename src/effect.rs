@@ -0,0 +1,508 @@
+use libc::{c_int, c_ulong, size_t, write};
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+use Error;
+
+/// The number of bytes in an `ff_effect` on this platform
+#[cfg(target_pointer_width = "64")]
+pub const FF_EFFECT_SIZE: usize = 48;
+/// The number of bytes in an `ff_effect` on this platform
+#[cfg(not(target_pointer_width = "64"))]
+pub const FF_EFFECT_SIZE: usize = 44;
+
+pub const FF_RUMBLE: u16 = 0x50;
+pub const FF_PERIODIC: u16 = 0x51;
+pub const FF_CONSTANT: u16 = 0x52;
+pub const FF_SPRING: u16 = 0x53;
+pub const FF_FRICTION: u16 = 0x54;
+pub const FF_DAMPER: u16 = 0x55;
+pub const FF_INERTIA: u16 = 0x56;
+pub const FF_RAMP: u16 = 0x57;
+
+const EV_FF: u16 = 0x15;
+
+extern {
+	fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+	fn dup(fd: c_int) -> c_int;
+	fn close(fd: c_int) -> c_int;
+}
+
+/// Build an `_IOW('E', nr, len)` request number
+fn eviocs(nr: c_ulong, len: usize) -> c_ulong {
+	(1 << 30) | ((len as c_ulong) << 16) | (0x45 << 8) | nr
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The shape of the wave a periodic effect plays
+pub enum Waveform {
+	/// A square wave
+	Square = 0x58,
+	/// A triangle wave
+	Triangle = 0x59,
+	/// A sine wave
+	Sine = 0x5a,
+	/// A sawtooth wave that rises
+	SawUp = 0x5b,
+	/// A sawtooth wave that falls
+	SawDown = 0x5c
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// How the strength of an effect fades in at the start and out at the end
+pub struct Envelope {
+	/// How long the effect takes to reach its level, in milliseconds
+	pub attack_length: u16,
+	/// The level the effect starts at
+	pub attack_level: u16,
+	/// How long the effect takes to fade out, in milliseconds
+	pub fade_length: u16,
+	/// The level the effect fades out to
+	pub fade_level: u16
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// How a condition effect, like a spring, pushes back on an axis
+pub struct Condition {
+	/// The most force applied when the axis is right of the centre
+	pub right_saturation: u16,
+	/// The most force applied when the axis is left of the centre
+	pub left_saturation: u16,
+	/// How quickly the force grows when the axis is right of the centre
+	pub right_coeff: i16,
+	/// How quickly the force grows when the axis is left of the centre
+	pub left_coeff: i16,
+	/// The size of the area around the centre where no force is applied
+	pub deadband: u16,
+	/// The position of the centre
+	pub center: i16
+}
+
+impl Condition {
+	/// Make a condition that pushes back the same way on both sides of the centre
+	pub fn symmetric(saturation: u16, coeff: i16) -> Condition {
+		Condition {
+			right_saturation: saturation,
+			left_saturation: saturation,
+			right_coeff: coeff,
+			left_coeff: coeff,
+			deadband: 0,
+			center: 0
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The kind of force an effect applies
+pub enum EffectKind {
+	/// Run the strong and weak rumble motors
+	Rumble {
+		/// The magnitude of the strong motor
+		strong: u16,
+		/// The magnitude of the weak motor
+		weak: u16
+	},
+	/// Push with a constant force
+	Constant {
+		/// The force
+		level: i16,
+		/// How the force fades in and out
+		envelope: Envelope
+	},
+	/// Push with a force that changes linearly over the length of the effect
+	Ramp {
+		/// The force at the start
+		start_level: i16,
+		/// The force at the end
+		end_level: i16,
+		/// How the force fades in and out
+		envelope: Envelope
+	},
+	/// Push with a force that follows a wave
+	Periodic {
+		/// The shape of the wave
+		waveform: Waveform,
+		/// The period of the wave in milliseconds
+		period: u16,
+		/// The peak force
+		magnitude: i16,
+		/// The force the wave is centred on
+		offset: i16,
+		/// Where the wave starts
+		phase: u16,
+		/// How the force fades in and out
+		envelope: Envelope
+	},
+	/// Push back towards the centre like a spring, with a condition for the
+	/// horizontal and vertical axes
+	Spring([Condition; 2]),
+	/// Resist movement in proportion to its velocity
+	Damper([Condition; 2]),
+	/// Resist movement like friction
+	Friction([Condition; 2]),
+	/// Resist movement in proportion to its acceleration
+	Inertia([Condition; 2])
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A force feedback effect, which is built up from its kind
+///
+/// The encoding matches the kernel's `struct ff_effect` byte for byte:
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::effect::{Effect, Envelope, FF_EFFECT_SIZE};
+/// let effect = Effect::constant(0x1234)
+///     .with_direction(0x4000)
+///     .with_length(Duration::from_millis(1000))
+///     .with_delay(Duration::from_millis(10))
+///     .with_envelope(Envelope { attack_length: 100, attack_level: 200, fade_length: 300, fade_level: 400 });
+/// let bytes = effect.encode(7);
+/// assert_eq!(bytes.len(), FF_EFFECT_SIZE);
+/// // type, id, direction, trigger button, trigger interval, length, delay
+/// assert_eq!(&bytes[..14], &[0x52, 0, 7, 0, 0, 0x40, 0, 0, 0, 0, 0xe8, 0x03, 10, 0]);
+/// // level, attack length, attack level, fade length, fade level
+/// assert_eq!(&bytes[16..26], &[0x34, 0x12, 100, 0, 200, 0, 0x2c, 1, 0x90, 1]);
+/// assert!(bytes[26..].iter().all(|&b| b == 0));
+/// ```
+pub struct Effect {
+	/// The kind of force the effect applies
+	pub kind: EffectKind,
+	/// The direction of the force, where 0 is down, 0x4000 is left, 0x8000 is up
+	/// and 0xC000 is right
+	pub direction: u16,
+	/// The button that plays the effect when it is pressed, if any
+	pub trigger_button: u16,
+	/// How long the trigger button has to be released for before it can play the
+	/// effect again, in milliseconds
+	pub trigger_interval: u16,
+	/// How long the effect plays for, in milliseconds, or 0 for forever
+	pub length: u16,
+	/// How long to wait before playing the effect, in milliseconds
+	pub delay: u16
+}
+
+/// Convert a duration into milliseconds that fit in a `u16`
+fn millis(duration: Duration) -> u16 {
+	let millis = duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1000000;
+	if millis > 0xFFFF { 0xFFFF } else { millis as u16 }
+}
+
+/// Write a 16-bit number into the start of `bytes` in native byte order
+fn put(bytes: &mut [u8], value: u16) {
+	let value: [u8; 2] = unsafe { mem::transmute(value) };
+	bytes[0] = value[0];
+	bytes[1] = value[1];
+}
+
+/// Write an envelope into the start of `bytes`
+fn put_envelope(bytes: &mut [u8], envelope: &Envelope) {
+	put(&mut bytes[0..], envelope.attack_length);
+	put(&mut bytes[2..], envelope.attack_level);
+	put(&mut bytes[4..], envelope.fade_length);
+	put(&mut bytes[6..], envelope.fade_level);
+}
+
+impl Effect {
+	/// Make an effect of a kind that plays forever in every direction as soon as it is played
+	pub fn new(kind: EffectKind) -> Effect {
+		Effect {
+			kind: kind,
+			direction: 0,
+			trigger_button: 0,
+			trigger_interval: 0,
+			length: 0,
+			delay: 0
+		}
+	}
+
+	/// Make an effect that runs the rumble motors
+	pub fn rumble(strong: u16, weak: u16) -> Effect {
+		Effect::new(EffectKind::Rumble { strong: strong, weak: weak })
+	}
+
+	/// Make an effect that pushes with a constant force
+	pub fn constant(level: i16) -> Effect {
+		Effect::new(EffectKind::Constant { level: level, envelope: Envelope::default() })
+	}
+
+	/// Make an effect with a force that changes from one level to another
+	pub fn ramp(start_level: i16, end_level: i16) -> Effect {
+		Effect::new(EffectKind::Ramp { start_level: start_level, end_level: end_level, envelope: Envelope::default() })
+	}
+
+	/// Make an effect with a force that follows a wave
+	pub fn periodic(waveform: Waveform, period: Duration, magnitude: i16) -> Effect {
+		Effect::new(EffectKind::Periodic {
+			waveform: waveform,
+			period: millis(period),
+			magnitude: magnitude,
+			offset: 0,
+			phase: 0,
+			envelope: Envelope::default()
+		})
+	}
+
+	/// Make an effect that centres both axes like a spring
+	pub fn spring(condition: Condition) -> Effect {
+		Effect::new(EffectKind::Spring([condition, condition]))
+	}
+
+	/// Make an effect that damps movement on both axes
+	pub fn damper(condition: Condition) -> Effect {
+		Effect::new(EffectKind::Damper([condition, condition]))
+	}
+
+	/// Set the direction of the force
+	pub fn with_direction(mut self, direction: u16) -> Effect {
+		self.direction = direction;
+		self
+	}
+
+	/// Set how long the effect plays for
+	pub fn with_length(mut self, length: Duration) -> Effect {
+		self.length = millis(length);
+		self
+	}
+
+	/// Set how long to wait before playing the effect
+	pub fn with_delay(mut self, delay: Duration) -> Effect {
+		self.delay = millis(delay);
+		self
+	}
+
+	/// Set the button that plays the effect and how long it has to be released for
+	/// before it can play the effect again
+	pub fn with_trigger(mut self, button: u16, interval: Duration) -> Effect {
+		self.trigger_button = button;
+		self.trigger_interval = millis(interval);
+		self
+	}
+
+	/// Set how the force fades in and out, if the kind of effect has an envelope
+	pub fn with_envelope(mut self, new: Envelope) -> Effect {
+		match self.kind {
+			EffectKind::Constant { ref mut envelope, .. } |
+			EffectKind::Ramp { ref mut envelope, .. } |
+			EffectKind::Periodic { ref mut envelope, .. } => *envelope = new,
+			_ => ()
+		}
+		self
+	}
+
+	/// Get the `FF_*` type of the effect
+	pub fn get_type(&self) -> u16 {
+		match self.kind {
+			EffectKind::Rumble { .. } => FF_RUMBLE,
+			EffectKind::Constant { .. } => FF_CONSTANT,
+			EffectKind::Ramp { .. } => FF_RAMP,
+			EffectKind::Periodic { .. } => FF_PERIODIC,
+			EffectKind::Spring(_) => FF_SPRING,
+			EffectKind::Damper(_) => FF_DAMPER,
+			EffectKind::Friction(_) => FF_FRICTION,
+			EffectKind::Inertia(_) => FF_INERTIA
+		}
+	}
+
+	/// Encode the effect as a `struct ff_effect` with the id given
+	///
+	/// The id is -1 for an effect that hasn't been uploaded yet. The kind of effect
+	/// is written into the union at byte 16:
+	///
+	/// ``` rust
+	/// use std::time::Duration;
+	/// use reminisce::effect::{Condition, Effect, EffectKind, Envelope, Waveform};
+	/// // strong and weak magnitudes
+	/// let bytes = Effect::rumble(0xC000, 0x4000).encode(-1);
+	/// assert_eq!(&bytes[..4], &[0x50, 0, 0xff, 0xff]);
+	/// assert_eq!(&bytes[16..20], &[0, 0xc0, 0, 0x40]);
+	/// assert!(bytes[20..].iter().all(|&b| b == 0));
+	///
+	/// // start level, end level and envelope
+	/// let bytes = Effect::ramp(-0x100, 0x7FFF)
+	///     .with_length(Duration::from_millis(500))
+	///     .with_envelope(Envelope { attack_length: 50, ..Envelope::default() })
+	///     .encode(1);
+	/// assert_eq!(&bytes[..4], &[0x57, 0, 1, 0]);
+	/// assert_eq!(&bytes[10..12], &[0xf4, 1]);
+	/// assert_eq!(&bytes[16..22], &[0, 0xff, 0xff, 0x7f, 50, 0]);
+	/// assert!(bytes[22..].iter().all(|&b| b == 0));
+	///
+	/// // waveform, period, magnitude, offset, phase and envelope
+	/// let bytes = Effect::new(EffectKind::Periodic {
+	///     waveform: Waveform::Sine,
+	///     period: 250,
+	///     magnitude: 0x1000,
+	///     offset: -2,
+	///     phase: 0x4000,
+	///     envelope: Envelope { attack_length: 0, attack_level: 0, fade_length: 0, fade_level: 0x100 }
+	/// }).encode(2);
+	/// assert_eq!(&bytes[..4], &[0x51, 0, 2, 0]);
+	/// assert_eq!(&bytes[16..34], &[0x5a, 0, 0xfa, 0, 0, 0x10, 0xfe, 0xff, 0, 0x40, 0, 0, 0, 0, 0, 0, 0, 1]);
+	/// assert!(bytes[34..].iter().all(|&b| b == 0));
+	///
+	/// // a condition for each axis, with the saturations, coefficients, deadband and centre
+	/// let vertical = Condition { right_saturation: 1, left_saturation: 2, right_coeff: -3, left_coeff: 4, deadband: 5, center: -6 };
+	/// let bytes = Effect::new(EffectKind::Spring([Condition::symmetric(0xFFFF, 0x2000), vertical])).encode(3);
+	/// assert_eq!(&bytes[..4], &[0x53, 0, 3, 0]);
+	/// assert_eq!(&bytes[16..28], &[0xff, 0xff, 0xff, 0xff, 0, 0x20, 0, 0x20, 0, 0, 0, 0]);
+	/// assert_eq!(&bytes[28..40], &[1, 0, 2, 0, 0xfd, 0xff, 4, 0, 5, 0, 0xfa, 0xff]);
+	/// assert!(bytes[40..].iter().all(|&b| b == 0));
+	///
+	/// // a damper uses the same condition for both axes
+	/// let bytes = Effect::damper(Condition::symmetric(0x8000, -0x100)).encode(4);
+	/// assert_eq!(&bytes[..4], &[0x55, 0, 4, 0]);
+	/// assert_eq!(&bytes[16..28], &[0, 0x80, 0, 0x80, 0, 0xff, 0, 0xff, 0, 0, 0, 0]);
+	/// assert_eq!(&bytes[16..28], &bytes[28..40]);
+	/// ```
+	pub fn encode(&self, id: i16) -> [u8; FF_EFFECT_SIZE] {
+		let mut bytes = [0; FF_EFFECT_SIZE];
+		put(&mut bytes[0..], self.get_type());
+		put(&mut bytes[2..], id as u16);
+		put(&mut bytes[4..], self.direction);
+		put(&mut bytes[6..], self.trigger_button);
+		put(&mut bytes[8..], self.trigger_interval);
+		put(&mut bytes[10..], self.length);
+		put(&mut bytes[12..], self.delay);
+		// The union is aligned to a pointer, or to 4 bytes on 32-bit platforms
+		let union = &mut bytes[16..];
+		match self.kind {
+			EffectKind::Rumble { strong, weak } => {
+				put(&mut union[0..], strong);
+				put(&mut union[2..], weak);
+			},
+			EffectKind::Constant { level, ref envelope } => {
+				put(&mut union[0..], level as u16);
+				put_envelope(&mut union[2..], envelope);
+			},
+			EffectKind::Ramp { start_level, end_level, ref envelope } => {
+				put(&mut union[0..], start_level as u16);
+				put(&mut union[2..], end_level as u16);
+				put_envelope(&mut union[4..], envelope);
+			},
+			EffectKind::Periodic { waveform, period, magnitude, offset, phase, ref envelope } => {
+				put(&mut union[0..], waveform as u16);
+				put(&mut union[2..], period);
+				put(&mut union[4..], magnitude as u16);
+				put(&mut union[6..], offset as u16);
+				put(&mut union[8..], phase);
+				put_envelope(&mut union[10..], envelope);
+			},
+			EffectKind::Spring(ref conditions) | EffectKind::Damper(ref conditions) |
+			EffectKind::Friction(ref conditions) | EffectKind::Inertia(ref conditions) => {
+				for (i, condition) in conditions.iter().enumerate() {
+					let bytes = &mut union[i * 12..];
+					put(&mut bytes[0..], condition.right_saturation);
+					put(&mut bytes[2..], condition.left_saturation);
+					put(&mut bytes[4..], condition.right_coeff as u16);
+					put(&mut bytes[6..], condition.left_coeff as u16);
+					put(&mut bytes[8..], condition.deadband);
+					put(&mut bytes[10..], condition.center as u16);
+				}
+			}
+		}
+		bytes
+	}
+}
+
+/// An effect that has been uploaded to a device
+///
+/// The effect is removed from the device when this is dropped.
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::Joystick;
+/// use reminisce::effect::{Condition, Effect, EffectHandle};
+/// use reminisce::evdev::NativeJoystick;
+/// if let Ok(wheel) = NativeJoystick::open(0) {
+///     // Centre the wheel while the game is running
+///     if let Ok(spring) = EffectHandle::upload(&wheel, &Effect::spring(Condition::symmetric(0xFFFF, 0x2000))) {
+///         spring.play(1).unwrap();
+///     }
+/// }
+/// ```
+pub struct EffectHandle {
+	fd: c_int,
+	id: i16
+}
+
+impl EffectHandle {
+	/// Upload an effect to a device with `EVIOCSFF`, which needs to have been opened for writing
+	pub fn upload<D>(device: &D, effect: &Effect) -> Result<EffectHandle, Error> where D:AsRawFd {
+		let fd = unsafe { dup(device.as_raw_fd()) };
+		if fd == -1 {
+			return Err(Error::last_os_error())
+		}
+		let mut handle = EffectHandle {
+			fd: fd,
+			id: -1
+		};
+		try!(handle.update(effect));
+		Ok(handle)
+	}
+
+	/// Get the id the device gave the effect
+	pub fn get_id(&self) -> i16 {
+		self.id
+	}
+
+	/// Replace the effect, which takes effect straight away if it is playing
+	pub fn update(&mut self, effect: &Effect) -> Result<(), Error> {
+		let mut bytes = effect.encode(self.id);
+		if unsafe { ioctl(self.fd, eviocs(0x80, FF_EFFECT_SIZE), bytes.as_mut_ptr()) } < 0 {
+			return Err(Error::last_os_error())
+		}
+		// The kernel writes the id it gave a new effect back into the struct
+		self.id = unsafe { mem::transmute([bytes[2], bytes[3]]) };
+		Ok(())
+	}
+
+	/// Play the effect a number of times
+	pub fn play(&self, count: i32) -> Result<(), Error> {
+		self.write(count)
+	}
+
+	/// Stop the effect
+	pub fn stop(&self) -> Result<(), Error> {
+		self.write(0)
+	}
+
+	/// Write an `EV_FF` event for the effect
+	fn write(&self, value: i32) -> Result<(), Error> {
+		#[repr(C)]
+		struct InputEvent {
+			time: [usize; 2],
+			_type: u16,
+			code: u16,
+			value: i32
+		}
+		let event = InputEvent {
+			time: [0, 0],
+			_type: EV_FF,
+			code: self.id as u16,
+			value: value
+		};
+		let size = mem::size_of::<InputEvent>() as size_t;
+		if unsafe { write(self.fd, &event as *const InputEvent as *const _, size) } == -1 {
+			Err(Error::last_os_error())
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl AsRawFd for EffectHandle {
+	fn as_raw_fd(&self) -> RawFd {
+		self.fd
+	}
+}
+
+impl Drop for EffectHandle {
+	/// Remove the effect from the device with `EVIOCRMFF`
+	fn drop(&mut self) {
+		unsafe {
+			ioctl(self.fd, eviocs(0x81, mem::size_of::<c_int>()), self.id as c_int);
+			close(self.fd);
+		}
+	}
+}
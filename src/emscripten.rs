@@ -1,10 +1,9 @@
 use libc::*;
 use std::borrow::Cow;
 use std::mem;
-use std::mem::transmute as cast;
-use std::io::Error;
 use std::ffi::CStr;
 use std::collections::VecDeque;
+use std::time::Duration;
 use Joystick;
 
 extern {
@@ -28,7 +27,12 @@ pub struct NativeEvent {
 
 pub struct NativeJoystick {
     last: NativeEvent,
-	events: VecDeque<::Event>
+	events: VecDeque<::TimedEvent>
+}
+
+/// Convert the event
+pub fn convert_event(event: ::TimedEvent) -> ::Event {
+    event.event
 }
 
 fn os_error(code: c_int) -> &'static str {
@@ -47,14 +51,13 @@ fn os_error(code: c_int) -> &'static str {
 
 impl ::Joystick for NativeJoystick {
     type WithState = NativeJoystick;
-    type NativeEvent = ::Event;
-    type OpenError = Error;
-    fn open(index: u8) -> Result<NativeJoystick, Error> {
+    type NativeEvent = ::TimedEvent;
+    fn open(index: u8) -> Result<NativeJoystick, ::Error> {
         unsafe {
             let mut state = mem::uninitialized();
             let code = emscripten_get_gamepad_status(index as c_int, &mut state);
             if code != 0 {
-                Err(Error::last_os_error())
+                Err(::Error::Backend(os_error(code).into()))
             } else {
                 Ok(NativeJoystick {
                     last: state,
@@ -88,18 +91,31 @@ impl ::Joystick for NativeJoystick {
     fn with_state(self) -> NativeJoystick {
         self
     }
-	fn poll_native(&mut self) -> Option<::Event> {
+	fn try_poll_native(&mut self) -> Result<Option<::TimedEvent>, ::Error> {
 		use ::StatefulJoystick;
 		self.update();
-		self.events.pop_back()
+		Ok(self.events.pop_back())
 	}
+    /// The browser timestamps gamepad states in milliseconds since the page loaded
+    fn get_event_time(event: &::TimedEvent) -> Duration {
+        event.time
+    }
+}
+impl ::Rumble for NativeJoystick {
+    /// The HTML5 Gamepad API doesn't support rumble
+    fn supports_rumble(&self) -> bool {
+        false
+    }
+    fn rumble(&mut self, _strong: f32, _weak: f32, _duration: Duration) -> Result<(), ::Error> {
+        Err(::Error::Unsupported)
+    }
 }
 impl ::StatefulJoystick for NativeJoystick {
     fn get_axis(&self, index: ::Axis) -> Option<i16> {
         self.get_normalised_axis(index).map(|axis| (axis * ::MAX_AXIS_VALUE as f32) as i16)
     }
     fn get_normalised_axis(&self, index: ::Axis) -> Option<f32> {
-        let index = unsafe { cast(index) };
+        let index = index as u8;
         if index < self.last.num_axes as u8 && index < 64 {
             Some(self.last.axis[index as usize] as f32)
         } else {
@@ -107,7 +123,16 @@ impl ::StatefulJoystick for NativeJoystick {
         }
     }
     fn get_button(&self, index: ::Button) -> Option<bool> {
-        let index = unsafe { cast(index) };
+        self.get_raw_button(index as u8)
+    }
+    fn get_raw_axis(&self, index: u8) -> Option<i16> {
+        if index < self.last.num_axes as u8 && index < 64 {
+            Some((self.last.axis[index as usize] as f32 * ::MAX_AXIS_VALUE as f32) as i16)
+        } else {
+            None
+        }
+    }
+    fn get_raw_button(&self, index: u8) -> Option<bool> {
         if index < self.last.num_buttons as u8 && index < 64 {
           Some(self.last.digital_button[index as usize])
         } else {
@@ -121,23 +146,32 @@ impl ::StatefulJoystick for NativeJoystick {
             {
                 let now = &state;
                 let last = &self.last;
+                let time = Duration::new((now.timestamp / 1000.0) as u64, ((now.timestamp % 1000.0) * 1000000.0) as u32);
+                let source = now.index as u8;
                 for button in (0..now.num_buttons) {
                     let now_btn = now.digital_button[button as usize];
                     let last_btn = last.digital_button[button as usize];
-                    if now_btn && !last_btn {
-                        let btn = cast(button as u8);
-            			self.events.push_back(::Event::ButtonPressed(btn))
-                    } else if !now_btn && last_btn {
-                        let btn = cast(button as u8);
-            			self.events.push_back(::Event::ButtonReleased(btn))
+                    if now_btn != last_btn {
+                        let index = button as u8;
+                        let event = match (::Button::from_index(index), now_btn) {
+                            (Some(btn), true) => ::Event::ButtonPressed(btn),
+                            (Some(btn), false) => ::Event::ButtonReleased(btn),
+                            (None, pressed) => ::Event::RawButton(index, pressed)
+                        };
+                        self.events.push_back(::TimedEvent { event: event, time: time, source: source })
                     }
                 }
                 for axis in (0..now.num_axes) {
                     let now_axis = now.axis[axis as usize];
                     let last_axis = last.axis[axis as usize];
                     if now_axis != last_axis {
-                        let axis = cast(axis as u8);
-                		self.events.push_back(::Event::AxisMoved(axis, (now_axis as f32 * ::MAX_AXIS_VALUE as f32) as i16))
+                        let index = axis as u8;
+                        let value = (now_axis as f32 * ::MAX_AXIS_VALUE as f32) as i16;
+                        let event = match ::Axis::from_index(index) {
+                            Some(axis) => ::Event::AxisMoved(axis, value),
+                            None => ::Event::RawAxisMoved(index, value)
+                        };
+                        self.events.push_back(::TimedEvent { event: event, time: time, source: source })
                     }
                 }
             }
@@ -0,0 +1,98 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+/// An error that occurred while opening or polling a joystick
+pub enum Error {
+	/// There is no joystick with the index given
+	NotFound,
+	/// The joystick was unplugged
+	Disconnected,
+	/// The process isn't allowed to open the joystick
+	///
+	/// On Linux, this usually means the user isn't in the `input` group.
+	PermissionDenied,
+	/// The device opened isn't a joystick
+	NotAJoystick,
+	/// The backend reported an event it doesn't know how to handle, with the
+	/// event's type and value
+	UnknownEvent(u16, i32),
+	/// The backend failed with a message
+	Backend(String),
+	/// A mapping couldn't be parsed, with a message explaining why
+	InvalidMapping(String),
+	/// A response curve couldn't be parsed, with a message explaining why
+	InvalidCurve(String),
+	/// An action binding couldn't be parsed, with a message explaining why
+	InvalidBinding(String),
+	/// The joystick or backend doesn't support the operation
+	Unsupported,
+	/// Some other I/O error occurred
+	Io(io::Error)
+}
+
+impl Error {
+	/// Get the error for the last OS error that occurred
+	pub fn last_os_error() -> Error {
+		io::Error::last_os_error().into()
+	}
+}
+
+impl From<io::Error> for Error {
+	/// Convert the I/O error into the most specific error possible
+	fn from(err: io::Error) -> Error {
+		match (err.kind(), err.raw_os_error()) {
+			(io::ErrorKind::NotFound, _) => Error::NotFound,
+			(io::ErrorKind::PermissionDenied, _) => Error::PermissionDenied,
+			(_, Some(code)) if cfg!(unix) => match code {
+				// ENXIO
+				6 => Error::NotFound,
+				// ENODEV
+				19 => Error::Disconnected,
+				// ENOTTY
+				25 => Error::NotAJoystick,
+				_ => Error::Io(err)
+			},
+			_ => Error::Io(err)
+		}
+	}
+}
+
+impl error::Error for Error {
+	fn description(&self) -> &str {
+		match *self {
+			Error::NotFound => "joystick not found",
+			Error::Disconnected => "joystick disconnected",
+			Error::PermissionDenied => "permission denied",
+			Error::NotAJoystick => "not a joystick",
+			Error::UnknownEvent(_, _) => "unknown event",
+			Error::Unsupported => "not supported",
+			Error::Backend(ref err) => err,
+			Error::InvalidMapping(ref err) => err,
+			Error::InvalidCurve(ref err) => err,
+			Error::InvalidBinding(ref err) => err,
+			Error::Io(ref err) => err.description()
+		}
+	}
+	fn cause(&self) -> Option<&error::Error> {
+		match *self {
+			Error::Io(ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::UnknownEvent(ty, value) => write!(fmt, "unknown event of type {} with value {}", ty, value),
+			Error::Backend(ref err) => write!(fmt, "{}", err),
+			Error::InvalidMapping(ref err) => write!(fmt, "invalid mapping: {}", err),
+			Error::InvalidCurve(ref err) => write!(fmt, "invalid curve: {}", err),
+			Error::InvalidBinding(ref err) => write!(fmt, "invalid binding: {}", err),
+			Error::Io(ref err) => write!(fmt, "{}", err),
+			_ => write!(fmt, "{}", error::Error::description(self))
+		}
+	}
+}
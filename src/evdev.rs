@@ -0,0 +1,819 @@
+use libc::{c_char, c_int, c_long, c_ulong, size_t, O_RDONLY, O_RDWR, O_NONBLOCK, F_GETFL, F_SETFL, fcntl, read};
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::time::Duration;
+use effect::{Effect, EffectHandle, EffectKind, FF_RUMBLE};
+use {Axis, Button, Error, HatDirection, Joystick, Rumble};
+
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const EV_FF: u16 = 0x15;
+const FF_CNT: usize = 0x80;
+
+const KEY_CNT: usize = 0x300;
+const ABS_CNT: usize = 0x40;
+const BTN_MISC: u16 = 0x100;
+const BTN_JOYSTICK: u16 = 0x120;
+const BTN_GAMEPAD: u16 = 0x130;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+/// The horizontal axis of the first hat switch
+pub const ABS_HAT0X: u16 = 0x10;
+/// The vertical axis of the first hat switch
+pub const ABS_HAT0Y: u16 = 0x11;
+
+const BTN_SOUTH: u16 = 0x130;
+const BTN_EAST: u16 = 0x131;
+const BTN_X: u16 = 0x133;
+const BTN_Y: u16 = 0x134;
+const BTN_TL: u16 = 0x136;
+const BTN_TR: u16 = 0x137;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const BTN_SELECT: u16 = 0x13a;
+const BTN_START: u16 = 0x13b;
+const BTN_THUMBL: u16 = 0x13d;
+const BTN_THUMBR: u16 = 0x13e;
+const BTN_DPAD_UP: u16 = 0x220;
+const BTN_DPAD_DOWN: u16 = 0x221;
+const BTN_DPAD_LEFT: u16 = 0x222;
+const BTN_DPAD_RIGHT: u16 = 0x223;
+
+const NAME_LEN: usize = 128;
+
+extern {
+	fn open(path: *const c_char, oflag: c_int) -> c_int;
+	fn close(fd: c_int) -> c_int;
+	fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+/// Build an `_IOC(_IOC_READ, 'E', nr, len)` request number
+fn eviocg(nr: c_ulong, len: usize) -> c_ulong {
+	(2 << 30) | ((len as c_ulong) << 16) | (0x45 << 8) | nr
+}
+
+fn eviocgname(len: usize) -> c_ulong {
+	eviocg(0x06, len)
+}
+
+fn eviocgkey(len: usize) -> c_ulong {
+	eviocg(0x18, len)
+}
+
+fn eviocgbit(ev: u16, len: usize) -> c_ulong {
+	eviocg(0x20 + ev as c_ulong, len)
+}
+
+fn eviocgabs(abs: u16) -> c_ulong {
+	eviocg(0x40 + abs as c_ulong, mem::size_of::<AbsInfo>())
+}
+
+fn test_bit(bits: &[u8], bit: u16) -> bool {
+	bits[bit as usize / 8] & (1 << (bit % 8)) != 0
+}
+
+/// Get the button a key code from `linux/input-event-codes.h` represents
+pub fn button_from_code(code: u16) -> Option<Button> {
+	Some(match code {
+		BTN_SOUTH => Button::A,
+		BTN_EAST => Button::B,
+		BTN_X => Button::X,
+		BTN_Y => Button::Y,
+		BTN_TL => Button::LeftShoulder,
+		BTN_TR => Button::RightShoulder,
+		BTN_TL2 => Button::LeftTrigger,
+		BTN_TR2 => Button::RightTrigger,
+		BTN_SELECT => Button::Select,
+		BTN_START => Button::Start,
+		BTN_THUMBL => Button::LeftStick,
+		BTN_THUMBR => Button::RightStick,
+		BTN_DPAD_UP => Button::DPadUp,
+		BTN_DPAD_DOWN => Button::DPadDown,
+		BTN_DPAD_LEFT => Button::DPadLeft,
+		BTN_DPAD_RIGHT => Button::DPadRight,
+		_ => return None
+	})
+}
+
+/// Get the key code from `linux/input-event-codes.h` that represents a button
+pub fn button_code(button: Button) -> u16 {
+	match button {
+		Button::A => BTN_SOUTH,
+		Button::B => BTN_EAST,
+		Button::X => BTN_X,
+		Button::Y => BTN_Y,
+		Button::LeftShoulder => BTN_TL,
+		Button::RightShoulder => BTN_TR,
+		Button::LeftTrigger => BTN_TL2,
+		Button::RightTrigger => BTN_TR2,
+		Button::Select => BTN_SELECT,
+		Button::Start => BTN_START,
+		Button::LeftStick => BTN_THUMBL,
+		Button::RightStick => BTN_THUMBR,
+		Button::DPadUp => BTN_DPAD_UP,
+		Button::DPadDown => BTN_DPAD_DOWN,
+		Button::DPadLeft => BTN_DPAD_LEFT,
+		Button::DPadRight => BTN_DPAD_RIGHT
+	}
+}
+
+/// Get the axis an absolute axis code from `linux/input-event-codes.h` represents
+pub fn axis_from_code(code: u16) -> Option<Axis> {
+	Some(match code {
+		ABS_X => Axis::LeftX,
+		ABS_Y => Axis::LeftY,
+		ABS_RX => Axis::RightX,
+		ABS_RY => Axis::RightY,
+		ABS_Z => Axis::TriggerLeft,
+		ABS_RZ => Axis::TriggerRight,
+		_ => return None
+	})
+}
+
+/// Get the index of the hat an absolute axis code is part of, and whether it is
+/// the vertical axis of the hat
+pub fn hat_from_code(code: u16) -> Option<(u8, bool)> {
+	if code >= ABS_HAT0X && code < ABS_HAT0X + 8 {
+		Some((((code - ABS_HAT0X) / 2) as u8, (code - ABS_HAT0X) % 2 == 1))
+	} else {
+		None
+	}
+}
+
+/// Get the absolute axis code from `linux/input-event-codes.h` that represents an axis
+pub fn axis_code(axis: Axis) -> u16 {
+	match axis {
+		Axis::LeftX => ABS_X,
+		Axis::LeftY => ABS_Y,
+		Axis::RightX => ABS_RX,
+		Axis::RightY => ABS_RY,
+		Axis::TriggerLeft => ABS_Z,
+		Axis::TriggerRight => ABS_RZ
+	}
+}
+
+/// Get the index of an event device from its device file name, like `event0`
+pub fn device_index(name: &str) -> Option<u8> {
+	if name.starts_with("event") {
+		name[5..].parse().ok()
+	} else {
+		None
+	}
+}
+
+/// Check if the event device with an index supports `FF_RUMBLE` effects
+///
+/// The device is only opened for reading while it is checked, so this works
+/// without write access to it.
+pub fn device_supports_rumble(index: u8) -> bool {
+	let path = CString::new(format!("/dev/input/event{}", index)).unwrap();
+	let mut ff = [0u8; FF_CNT / 8];
+	unsafe {
+		let fd = open(path.as_ptr(), O_RDONLY | O_NONBLOCK);
+		if fd == -1 {
+			return false
+		}
+		let result = ioctl(fd, eviocgbit(EV_FF, ff.len()), ff.as_mut_ptr());
+		close(fd);
+		result >= 0 && test_bit(&ff, FF_RUMBLE)
+	}
+}
+
+/// Scan for joysticks
+///
+/// This only returns event devices that have joystick or gamepad buttons.
+pub fn scan() -> Vec<NativeJoystick> {
+	use std::fs;
+	let mut joysticks = Vec::with_capacity(4);
+	if let Ok(entries) = fs::read_dir("/dev/input/") {
+		for entry in entries {
+			if let Ok(entry) = entry {
+				let path = entry.path();
+				let index = path.file_name()
+					.and_then(|name| name.to_str())
+					.and_then(device_index);
+				if let Some(index) = index {
+					if let Ok(js) = Joystick::open(index) {
+						joysticks.push(js)
+					}
+				}
+			}
+		}
+	}
+	joysticks
+}
+
+/// The range of an absolute axis as reported by `EVIOCGABS`
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct AbsInfo {
+	/// The current value
+	pub value: i32,
+	/// The minimum value
+	pub minimum: i32,
+	/// The maximum value
+	pub maximum: i32,
+	/// The noise filtered out by the kernel
+	pub fuzz: i32,
+	/// The range around the centre that is reported as the centre
+	pub flat: i32,
+	/// The resolution in units per millimetre or radian
+	pub resolution: i32
+}
+impl AbsInfo {
+	/// Scale a value from this range to between `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE`
+	pub fn normalise(&self, value: i32) -> i16 {
+		let (min, max) = (self.minimum as i64, self.maximum as i64);
+		if max <= min {
+			return value.max(::MIN_AXIS_VALUE as i32).min(::MAX_AXIS_VALUE as i32) as i16
+		}
+		let value = (value as i64).max(min).min(max);
+		let range = (::MAX_AXIS_VALUE as i64) - (::MIN_AXIS_VALUE as i64);
+		((value - min) * range / (max - min) + ::MIN_AXIS_VALUE as i64) as i16
+	}
+
+	/// Scale a value between `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE` to this range,
+	/// which undoes `normalise`
+	pub fn denormalise(&self, value: i16) -> i32 {
+		let (min, max) = (self.minimum as i64, self.maximum as i64);
+		if max <= min {
+			return value as i32
+		}
+		let value = (value as i64).max(::MIN_AXIS_VALUE as i64);
+		let range = (::MAX_AXIS_VALUE as i64) - (::MIN_AXIS_VALUE as i64);
+		((value - ::MIN_AXIS_VALUE as i64) * (max - min) / range + min) as i32
+	}
+}
+
+/// Represents a joystick opened through the evdev interface
+///
+/// Unlike the legacy joystick interface, this exposes the key and axis codes of the
+/// device, which are translated into Reminisce buttons and axes.
+pub struct NativeJoystick {
+	index: u8,
+	fd: c_int,
+	connected: bool,
+	name: String,
+	axes: Vec<(u16, AbsInfo)>,
+	buttons: Vec<u16>,
+	hats: [(i16, i16); 4],
+	ff: [u8; FF_CNT / 8],
+	rumble: Option<EffectHandle>
+}
+
+impl NativeJoystick {
+	/// Query the device behind `fd` for its name and capabilities
+	///
+	/// If the descriptor is not an event device (like a pipe) it is treated as a device
+	/// with no name and no capabilities.
+	fn from_fd(fd: c_int, index: u8) -> NativeJoystick {
+		let mut name = [0 as c_char; NAME_LEN];
+		let mut keys = [0u8; KEY_CNT / 8];
+		let mut abs = [0u8; ABS_CNT / 8];
+		let mut ff = [0u8; FF_CNT / 8];
+		let name = unsafe {
+			if ioctl(fd, eviocgname(NAME_LEN), name.as_mut_ptr()) < 0 {
+				String::new()
+			} else {
+				name[NAME_LEN - 1] = 0;
+				CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned()
+			}
+		};
+		unsafe {
+			ioctl(fd, eviocgbit(EV_KEY, keys.len()), keys.as_mut_ptr());
+			ioctl(fd, eviocgbit(EV_ABS, abs.len()), abs.as_mut_ptr());
+			ioctl(fd, eviocgbit(EV_FF, ff.len()), ff.as_mut_ptr());
+		}
+		let buttons = (BTN_MISC..KEY_CNT as u16).filter(|&code| test_bit(&keys, code)).collect();
+		let axes = (0..ABS_CNT as u16).filter(|&code| test_bit(&abs, code)).filter_map(|code| unsafe {
+			let mut info: AbsInfo = mem::zeroed();
+			if ioctl(fd, eviocgabs(code), &mut info as *mut AbsInfo) < 0 {
+				None
+			} else {
+				Some((code, info))
+			}
+		}).collect();
+		let mut js = NativeJoystick {
+			index: index,
+			fd: fd,
+			connected: true,
+			name: name,
+			axes: axes,
+			buttons: buttons,
+			hats: [(0, 0); 4],
+			ff: ff,
+			rumble: None
+		};
+		for i in 0..js.axes.len() {
+			let (code, value) = (js.axes[i].0, js.axes[i].1.value);
+			js.update_hat(code, value);
+		}
+		js
+	}
+
+	/// Check if the device has any of the buttons a joystick or gamepad has
+	fn is_joystick(&self) -> bool {
+		self.buttons.iter().any(|&code| code >= BTN_JOYSTICK && code < BTN_GAMEPAD + 0x10)
+	}
+
+	/// Get the key codes of the buttons this device has
+	pub fn get_button_codes(&self) -> &[u16] {
+		&self.buttons
+	}
+
+	/// Get the absolute axis codes this device has along with their ranges
+	pub fn get_axis_info(&self) -> &[(u16, AbsInfo)] {
+		&self.axes
+	}
+
+	/// Check if the device can play an effect
+	///
+	/// Periodic effects also need the device to support their waveform.
+	pub fn supports_effect(&self, effect: &Effect) -> bool {
+		let supported = test_bit(&self.ff, effect.get_type());
+		match effect.kind {
+			EffectKind::Periodic { waveform, .. } => supported && test_bit(&self.ff, waveform as u16),
+			_ => supported
+		}
+	}
+
+	/// Upload an effect to the device so it can be played, which needs the device
+	/// to be writable
+	pub fn upload_effect(&self, effect: &Effect) -> Result<EffectHandle, Error> {
+		if !self.supports_effect(effect) {
+			return Err(Error::Unsupported)
+		}
+		EffectHandle::upload(self, effect)
+	}
+
+	/// Record the value of an axis if it is part of a hat and get the direction the
+	/// hat is now pushed in
+	fn update_hat(&mut self, code: u16, value: i32) -> Option<(u8, HatDirection)> {
+		hat_from_code(code).map(|(hat, vertical)| {
+			let position = &mut self.hats[hat as usize];
+			let sign = if value < 0 { -1 } else if value > 0 { 1 } else { 0 };
+			if vertical {
+				position.1 = sign
+			} else {
+				position.0 = sign
+			}
+			(hat, HatDirection::from_axes(position.0, position.1))
+		})
+	}
+
+	/// Get the direction a hat is pushed in, if the device has it
+	fn get_hat(&self, hat: u8) -> Option<HatDirection> {
+		let code = ABS_HAT0X + hat as u16 * 2;
+		if hat < 4 && self.axes.iter().any(|&(c, _)| c == code || c == code + 1) {
+			let (x, y) = self.hats[hat as usize];
+			Some(HatDirection::from_axes(x, y))
+		} else {
+			None
+		}
+	}
+
+	/// Get the index of the first axis with a code that represents `axis`
+	fn axis_index(&self, axis: Axis) -> Option<usize> {
+		self.axes.iter().position(|&(code, _)| axis_from_code(code) == Some(axis))
+	}
+
+	/// Get the index of the first button with a code that represents `button`
+	fn button_index(&self, button: Button) -> Option<usize> {
+		self.buttons.iter().position(|&code| button_from_code(code) == Some(button))
+	}
+}
+
+impl FromRawFd for NativeJoystick {
+	/// Wrap an already opened event device or a pipe of recorded `input_event` records
+	///
+	/// The descriptor is switched to non-blocking mode and is closed when the joystick
+	/// is dropped. Axis values for axes the descriptor doesn't report a range for are
+	/// clamped to between `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE`.
+	unsafe fn from_raw_fd(fd: RawFd) -> NativeJoystick {
+		fcntl(fd, F_SETFL, fcntl(fd, F_GETFL) | O_NONBLOCK);
+		NativeJoystick::from_fd(fd, 0)
+	}
+}
+
+impl AsRawFd for NativeJoystick {
+	fn as_raw_fd(&self) -> RawFd {
+		self.fd
+	}
+}
+
+impl ::Joystick for NativeJoystick {
+	type WithState = StatefulNativeJoystick;
+	type NativeEvent = InputEvent;
+	/// This tries to open the interface `/dev/input/event...` and will return the
+	/// OS-level error if it fails to open this or `Error::NotAJoystick` if the
+	/// device isn't a joystick
+	fn open(index: u8) -> Result<NativeJoystick, Error> {
+		let path = format!("/dev/input/event{}", index);
+		let c_path = CString::new(path.as_bytes()).unwrap();
+		// Rumble needs write access, but reading is enough for everything else
+		let mut fd = unsafe { open(c_path.as_ptr(), O_RDWR | O_NONBLOCK) };
+		if fd == -1 {
+			fd = unsafe { open(c_path.as_ptr(), O_RDONLY | O_NONBLOCK) };
+		}
+		if fd == -1 {
+			return Err(Error::last_os_error())
+		}
+		let js = NativeJoystick::from_fd(fd, index);
+		if js.is_joystick() {
+			Ok(js)
+		} else {
+			Err(Error::NotAJoystick)
+		}
+	}
+	/// This reads from the device in non-blocking mode, skipping any events that
+	/// can't be translated into a Reminisce event, and scales axis values to between
+	/// `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE`
+	///
+	/// If the joystick has been unplugged, this returns `Error::Disconnected`
+	/// and `is_connected` will return false from then on.
+	fn try_poll_native(&mut self) -> Result<Option<InputEvent>, Error> {
+		unsafe {
+			let mut event: RawInputEvent = mem::zeroed();
+			loop {
+				let event_size = mem::size_of::<RawInputEvent>() as size_t;
+				let size = read(self.fd, &mut event as *mut RawInputEvent as *mut _, event_size);
+				if size == -1 {
+					let err = ::std::io::Error::last_os_error();
+					return match err.raw_os_error() {
+						Some(11) => Ok(None),
+						Some(19) => {
+							self.connected = false;
+							Err(Error::Disconnected)
+						},
+						_ => Err(err.into())
+					}
+				} else if size == 0 {
+					return Ok(None)
+				}
+				let mut hat = None;
+				let (kind, index, value, known) = match event._type {
+					EV_KEY if event.value != 2 => {
+						let index = self.buttons.iter().position(|&code| code == event.code);
+						(InputKind::Key, index, event.value, button_from_code(event.code).is_some())
+					},
+					EV_ABS => {
+						let index = self.axes.iter().position(|&(code, _)| code == event.code);
+						// Axes without a known range are clamped so they fit in an axis value
+						let value = match index {
+							Some(index) => self.axes[index].1.normalise(event.value) as i32,
+							None => event.value.max(::MIN_AXIS_VALUE as i32).min(::MAX_AXIS_VALUE as i32)
+						};
+						hat = self.update_hat(event.code, event.value);
+						(InputKind::Abs, index, value, hat.is_some() || axis_from_code(event.code).is_some())
+					},
+					_ => continue
+				};
+				if known || index.is_some() {
+					return Ok(Some(InputEvent {
+						sec: event.sec,
+						usec: event.usec,
+						kind: kind,
+						code: event.code,
+						value: value,
+						index: index.map(|i| i as u8),
+						hat: hat
+					}))
+				}
+			}
+		}
+	}
+	/// The kernel timestamps events with the time of day, or the monotonic
+	/// clock if it has been requested with `EVIOCSCLOCKID`
+	fn get_event_time(event: &InputEvent) -> Duration {
+		Duration::new(event.sec as u64, event.usec as u32 * 1000)
+	}
+	fn is_connected(&self) -> bool {
+		self.connected
+	}
+	fn get_num_axes(&self) -> u8 {
+		self.axes.len() as u8
+	}
+	fn get_num_buttons(&self) -> u8 {
+		self.buttons.len() as u8
+	}
+	fn get_id(&self) -> Cow<str> {
+		self.name[..].into()
+	}
+	fn get_index(&self) -> u8 {
+		self.index
+	}
+	/// This reads the information from `/sys/class/input/event{index}/device`
+	fn get_info(&self) -> ::DeviceInfo {
+		let path = format!("/sys/class/input/event{}/device", self.index);
+		::DeviceInfo::from_sysfs(path).unwrap_or_else(|_| ::DeviceInfo::from_name(self.get_id().into_owned()))
+	}
+	/// This reads the power supply of the device in `/sys/class/power_supply`
+	fn get_battery(&self) -> Option<f32> {
+		let path = format!("/sys/class/input/event{}/device", self.index);
+		::battery::PowerSupply::find(path).and_then(|supply| supply.get_level())
+	}
+	/// This reads the power supply of the device in `/sys/class/power_supply`
+	fn get_battery_state(&self) -> Option<::BatteryState> {
+		::battery::read_state(format!("/sys/class/input/event{}/device", self.index))
+	}
+	/// SDL numbers the joystick and gamepad buttons before the miscellaneous ones
+	#[cfg(feature = "mappings")]
+	fn get_mapping_button(&self, number: u8) -> Option<::mapper::Source> {
+		let buttons = self.buttons.iter().enumerate();
+		let mut ordered = buttons.clone().filter(|&(_, &code)| code >= BTN_JOYSTICK).chain(buttons.filter(|&(_, &code)| code < BTN_JOYSTICK));
+		ordered.nth(number as usize).map(|(index, &code)| match button_from_code(code) {
+			Some(button) => ::mapper::Source::Button(button),
+			None => ::mapper::Source::RawButton(index as u8)
+		})
+	}
+	/// SDL skips the axes of hats when it numbers the axes
+	#[cfg(feature = "mappings")]
+	fn get_mapping_axis(&self, number: u8) -> Option<::mapper::Source> {
+		let full = ::mapper::AxisRange::Full;
+		let mut axes = self.axes.iter().enumerate().filter(|&(_, &(code, _))| hat_from_code(code).is_none());
+		axes.nth(number as usize).map(|(index, &(code, _))| match axis_from_code(code) {
+			Some(axis) => ::mapper::Source::Axis(axis, full),
+			None => ::mapper::Source::RawAxis(index as u8, full)
+		})
+	}
+	fn with_state(self) -> StatefulNativeJoystick {
+		StatefulNativeJoystick::wrap(self)
+	}
+}
+
+impl Rumble for NativeJoystick {
+	/// This checks if the device supports `FF_RUMBLE` effects
+	fn supports_rumble(&self) -> bool {
+		test_bit(&self.ff, FF_RUMBLE)
+	}
+	/// This uploads an `FF_RUMBLE` effect and plays it, which needs the device to
+	/// be writable
+	///
+	/// The effect is reused every time this is called, so only one rumble plays at a time.
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+		if !self.supports_rumble() {
+			return Err(Error::Unsupported)
+		}
+		let magnitude = |value: f32| (value.max(0.0).min(1.0) * 65535.0) as u16;
+		let effect = Effect::rumble(magnitude(strong), magnitude(weak)).with_length(duration);
+		if let Some(ref mut handle) = self.rumble {
+			try!(handle.update(&effect));
+		} else {
+			self.rumble = Some(try!(EffectHandle::upload(self, &effect)));
+		}
+		self.rumble.as_ref().unwrap().play(1)
+	}
+}
+
+impl Drop for NativeJoystick {
+	/// Close the joystick's file descriptor
+	///
+	/// Any error is ignored because the descriptor is released either way.
+	fn drop(&mut self) {
+		unsafe {
+			close(self.fd);
+		}
+	}
+}
+
+/// The evdev joystick that tracks its state
+///
+/// The state is seeded from the kernel's view of the device when it is wrapped,
+/// so buttons that are already held and sticks that rest off-centre are reported
+/// correctly. Every axis and button the device has is tracked, including those
+/// that don't have an `Axis` or `Button`.
+pub struct StatefulNativeJoystick {
+	js: NativeJoystick,
+	axes: Vec<i16>,
+	buttons: Vec<bool>,
+	axis_times: Vec<Option<Duration>>,
+	button_times: Vec<Option<Duration>>
+}
+impl StatefulNativeJoystick {
+	/// Wrap a joystick
+	pub fn wrap(js: NativeJoystick) -> StatefulNativeJoystick {
+		let mut keys = [0u8; KEY_CNT / 8];
+		let has_keys = unsafe { ioctl(js.fd, eviocgkey(keys.len()), keys.as_mut_ptr()) >= 0 };
+		StatefulNativeJoystick {
+			axes: js.axes.iter().map(|&(_, ref info)| info.normalise(info.value)).collect(),
+			buttons: js.buttons.iter().map(|&code| has_keys && test_bit(&keys, code)).collect(),
+			axis_times: vec![None; js.axes.len()],
+			button_times: vec![None; js.buttons.len()],
+			js: js
+		}
+	}
+}
+impl AsRawFd for StatefulNativeJoystick {
+	fn as_raw_fd(&self) -> RawFd {
+		self.js.as_raw_fd()
+	}
+}
+impl ::Joystick for StatefulNativeJoystick {
+	type WithState = StatefulNativeJoystick;
+	type NativeEvent = InputEvent;
+
+	fn open(index: u8) -> Result<StatefulNativeJoystick, Error> {
+		::Joystick::open(index).map(|js| StatefulNativeJoystick::wrap(js))
+	}
+	fn is_connected(&self) -> bool {
+		self.js.is_connected()
+	}
+	fn get_id(&self) -> Cow<str> {
+		self.js.get_id()
+	}
+	fn get_info(&self) -> ::DeviceInfo {
+		self.js.get_info()
+	}
+	fn get_index(&self) -> u8 {
+		self.js.get_index()
+	}
+	fn get_num_axes(&self) -> u8 {
+		self.js.get_num_axes()
+	}
+	fn get_num_buttons(&self) -> u8 {
+		self.js.get_num_buttons()
+	}
+	fn get_battery(&self) -> Option<f32> {
+		self.js.get_battery()
+	}
+	fn get_battery_state(&self) -> Option<::BatteryState> {
+		self.js.get_battery_state()
+	}
+	fn try_poll_native(&mut self) -> Result<Option<InputEvent>, Error> {
+		let event = try!(self.js.try_poll_native());
+		if let Some(ref event) = event {
+			let time = Some(NativeJoystick::get_event_time(event));
+			match (event.kind, event.index) {
+				(InputKind::Key, Some(index)) => {
+					self.buttons[index as usize] = event.value != 0;
+					self.button_times[index as usize] = time;
+				},
+				(InputKind::Abs, Some(index)) => {
+					self.axes[index as usize] = event.value as i16;
+					self.axis_times[index as usize] = time;
+				},
+				_ => ()
+			}
+		}
+		Ok(event)
+	}
+	fn get_event_time(event: &InputEvent) -> Duration {
+		NativeJoystick::get_event_time(event)
+	}
+	#[cfg(feature = "mappings")]
+	fn get_mapping_button(&self, number: u8) -> Option<::mapper::Source> {
+		self.js.get_mapping_button(number)
+	}
+	#[cfg(feature = "mappings")]
+	fn get_mapping_axis(&self, number: u8) -> Option<::mapper::Source> {
+		self.js.get_mapping_axis(number)
+	}
+	fn with_state(self) -> StatefulNativeJoystick {
+		self
+	}
+}
+impl Rumble for StatefulNativeJoystick {
+	fn supports_rumble(&self) -> bool {
+		self.js.supports_rumble()
+	}
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+		self.js.rumble(strong, weak, duration)
+	}
+}
+impl ::StatefulJoystick for StatefulNativeJoystick {
+	fn get_axis(&self, index: Axis) -> Option<i16> {
+		self.js.axis_index(index).map(|i| self.axes[i])
+	}
+	fn get_button(&self, index: Button) -> Option<bool> {
+		self.js.button_index(index).map(|i| self.buttons[i])
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.axes.get(index as usize).cloned()
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.buttons.get(index as usize).cloned()
+	}
+	fn get_axis_time(&self, index: Axis) -> Option<Duration> {
+		self.js.axis_index(index).and_then(|i| self.axis_times[i])
+	}
+	fn get_button_time(&self, index: Button) -> Option<Duration> {
+		self.js.button_index(index).and_then(|i| self.button_times[i])
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.js.get_hat(index)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
+
+/// An `input_event` record as read from an event device
+#[repr(C)]
+struct RawInputEvent {
+	sec: c_long,
+	usec: c_long,
+	_type: u16,
+	code: u16,
+	value: i32
+}
+
+/// The types of event that are read from an event device
+#[derive(Copy, Clone, PartialEq)]
+enum InputKind {
+	/// `EV_KEY`
+	Key,
+	/// `EV_ABS`
+	Abs
+}
+
+/// An event read from an event device
+pub struct InputEvent {
+	/// timestamp seconds
+	sec: c_long,
+	/// timestamp microseconds
+	usec: c_long,
+	/// event type, which is only a key or an absolute axis
+	kind: InputKind,
+	/// key / axis code
+	code: u16,
+	/// value, which is scaled for axes
+	value: i32,
+	/// the index of the button / axis in the device's buttons / axes
+	index: Option<u8>,
+	/// the hat the axis is part of and the direction it is now pushed in
+	hat: Option<(u8, HatDirection)>
+}
+
+/// Convert the event
+///
+/// Hat switches are converted into `Event::HatMoved`, and axes and buttons with
+/// codes that don't have an `Axis` or `Button` are converted into raw events
+/// with their index on the device. Only key and absolute axis events are
+/// returned by `try_poll_native`, and axis values are already scaled or clamped
+/// to between `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE`.
+///
+/// Recorded `input_event` records can be converted by writing them into a pipe:
+///
+/// ``` rust
+/// extern crate libc;
+/// extern crate reminisce;
+/// use std::mem;
+/// use std::os::unix::io::FromRawFd;
+/// use std::time::Duration;
+/// use reminisce::{Axis, Button, Event, HatDirection, Joystick};
+/// use reminisce::evdev::NativeJoystick;
+/// #[repr(C)]
+/// struct InputEvent {
+///     sec: libc::c_long,
+///     usec: libc::c_long,
+///     kind: u16,
+///     code: u16,
+///     value: i32
+/// }
+/// # fn main() {
+/// let recording = [
+///     InputEvent { sec: 5, usec: 250000, kind: 0x01, code: 0x130, value: 1 },
+///     InputEvent { sec: 5, usec: 250000, kind: 0x00, code: 0, value: 0 },
+///     InputEvent { sec: 5, usec: 260000, kind: 0x03, code: 0x00, value: 100000 },
+///     InputEvent { sec: 5, usec: 270000, kind: 0x03, code: 0x10, value: -1 },
+///     InputEvent { sec: 5, usec: 280000, kind: 0x01, code: 0x130, value: 0 }
+/// ];
+/// let mut fds = [0; 2];
+/// let mut js = unsafe {
+///     assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+///     let size = mem::size_of_val(&recording);
+///     assert_eq!(libc::write(fds[1], recording.as_ptr() as *const _, size), size as isize);
+///     libc::close(fds[1]);
+///     NativeJoystick::from_raw_fd(fds[0])
+/// };
+/// let first = js.poll_timed().unwrap();
+/// assert_eq!(first.event, Event::ButtonPressed(Button::A));
+/// assert_eq!(first.time, Duration::from_millis(5250));
+/// assert_eq!(js.poll(), Some(Event::AxisMoved(Axis::LeftX, 32767)));
+/// assert_eq!(js.poll(), Some(Event::HatMoved(0, HatDirection::Left)));
+/// assert_eq!(js.poll(), Some(Event::ButtonReleased(Button::A)));
+/// assert_eq!(js.poll(), None);
+/// # }
+/// ```
+pub fn convert_event(event: InputEvent) -> ::Event {
+	let raw = event.index.unwrap_or(0xFF);
+	match event.kind {
+		InputKind::Key => match (button_from_code(event.code), event.value) {
+			(Some(button), 0) => ::Event::ButtonReleased(button),
+			(Some(button), _) => ::Event::ButtonPressed(button),
+			(None, value) => ::Event::RawButton(raw, value != 0)
+		},
+		InputKind::Abs => match (event.hat, axis_from_code(event.code)) {
+			(Some((hat, direction)), _) => ::Event::HatMoved(hat, direction),
+			(None, Some(axis)) => ::Event::AxisMoved(axis, event.value as i16),
+			(None, None) => ::Event::RawAxisMoved(raw, event.value as i16)
+		}
+	}
+}
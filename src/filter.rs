@@ -0,0 +1,377 @@
+use {Axis, Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent};
+use {MAX_AXIS_VALUE, MIN_AXIS_VALUE, NUM_AXES};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+/// The time between events assumed by the one euro filter when two events have
+/// the same time, in seconds
+static DEFAULT_INTERVAL: f32 = 1.0 / 120.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A filter that smooths the values of an axis or drops changes that are too small
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::{Axis, Event, Filter, Filters, Joystick, VirtualJoystick};
+/// for &filter in &[Filter::MovingAverage(16), Filter::Exponential(0.1), Filter::one_euro(1.0, 0.0)] {
+///     let js = VirtualJoystick::new(0, "Arcade Stick");
+///     let controller = js.controller();
+///     let mut js = js.with_filters(Filters::all(filter));
+///     // A stick held at -20000 whose reading jitters by up to 400 either way
+///     let mut seed = 1u32;
+///     for _ in 0..200 {
+///         seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+///         controller.move_axis(Axis::LeftY, -20000 + ((seed >> 16) % 801) as i16 - 400);
+///         controller.advance(Duration::from_millis(8));
+///     }
+///     let values: Vec<i16> = js.iter().filter_map(|event| match event {
+///         Event::AxisMoved(_, value) => Some(value),
+///         _ => None
+///     }).skip(50).collect();
+///     let spread = values.iter().max().unwrap() - values.iter().min().unwrap();
+///     assert!(spread < 400, "{:?} spread by {}", filter, spread);
+/// }
+/// ```
+pub enum Filter {
+	/// Report the average of the last number of values
+	MovingAverage(usize),
+	/// Move towards each new value by a fraction between 0 and 1 of the distance
+	/// to it, so smaller fractions smooth more
+	Exponential(f32),
+	/// Smooth slow movement a lot and fast movement a little, so the axis is steady
+	/// while it is held still without lagging behind when it moves quickly
+	///
+	/// This is the 1€ filter by Casiez, Roussel and Vogel. The cutoffs are in hertz
+	/// and the speed is measured in fractions of the range of the axis per second.
+	OneEuro {
+		/// The cutoff frequency while the axis isn't moving, where lower is smoother
+		min_cutoff: f32,
+		/// How much the cutoff frequency rises as the axis moves faster
+		beta: f32,
+		/// The cutoff frequency used to smooth the speed of the axis
+		derivative_cutoff: f32
+	},
+	/// Only report a value once it is at least this far from the last value reported
+	///
+	/// The centre and both ends of the axis are always reported, so the axis can
+	/// still rest at zero and be pushed all the way.
+	Hysteresis(i16)
+}
+
+impl Filter {
+	/// Make a one euro filter with a derivative cutoff of 1Hz
+	pub fn one_euro(min_cutoff: f32, beta: f32) -> Filter {
+		Filter::OneEuro {
+			min_cutoff: min_cutoff,
+			beta: beta,
+			derivative_cutoff: 1.0
+		}
+	}
+}
+
+/// Get the time in seconds
+fn seconds(time: Duration) -> f32 {
+	time.as_secs() as f32 + time.subsec_nanos() as f32 / 1e9
+}
+
+/// Get the smoothing factor of a low pass filter with a cutoff frequency
+fn smoothing(cutoff: f32, interval: f32) -> f32 {
+	let tau = 1.0 / (2.0 * PI * cutoff);
+	1.0 / (1.0 + tau / interval)
+}
+
+/// The values a filter remembers between events
+enum FilterState {
+	MovingAverage(VecDeque<f32>),
+	Exponential(f32),
+	OneEuro {
+		value: f32,
+		speed: f32,
+		time: Duration
+	},
+	Hysteresis(f32)
+}
+
+impl FilterState {
+	/// Start filtering from an initial value
+	fn new(filter: &Filter, value: f32, time: Duration) -> FilterState {
+		match *filter {
+			Filter::MovingAverage(count) => {
+				let mut values = VecDeque::with_capacity(count);
+				values.push_back(value);
+				FilterState::MovingAverage(values)
+			},
+			Filter::Exponential(_) => FilterState::Exponential(value),
+			Filter::OneEuro { .. } => FilterState::OneEuro {
+				value: value,
+				speed: 0.0,
+				time: time
+			},
+			Filter::Hysteresis(_) => FilterState::Hysteresis(value)
+		}
+	}
+
+	/// Filter the next value
+	fn apply(&mut self, filter: &Filter, input: f32, now: Duration) -> f32 {
+		match (self, *filter) {
+			(&mut FilterState::MovingAverage(ref mut values), Filter::MovingAverage(count)) => {
+				values.push_back(input);
+				while values.len() > count.max(1) {
+					values.pop_front();
+				}
+				values.iter().fold(0.0, |sum, value| sum + value) / values.len() as f32
+			},
+			(&mut FilterState::Exponential(ref mut value), Filter::Exponential(fraction)) => {
+				*value += (input - *value) * fraction.max(0.0).min(1.0);
+				*value
+			},
+			(&mut FilterState::OneEuro { ref mut value, ref mut speed, ref mut time },
+				Filter::OneEuro { min_cutoff, beta, derivative_cutoff }) => {
+				let interval = match now.checked_sub(*time).map(seconds) {
+					Some(interval) if interval > 0.0 => interval,
+					_ => DEFAULT_INTERVAL
+				};
+				*time = now;
+				let new_speed = (input - *value) / MAX_AXIS_VALUE as f32 / interval;
+				*speed += (new_speed - *speed) * smoothing(derivative_cutoff, interval);
+				let cutoff = min_cutoff + beta * speed.abs();
+				*value += (input - *value) * smoothing(cutoff, interval);
+				*value
+			},
+			(&mut FilterState::Hysteresis(ref mut value), Filter::Hysteresis(delta)) => {
+				let ends = input == 0.0 || input >= MAX_AXIS_VALUE as f32 || input <= MIN_AXIS_VALUE as f32;
+				if ends || (input - *value).abs() >= delta as f32 {
+					*value = input
+				}
+				*value
+			},
+			_ => input
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The filters of every `Axis` of a joystick, which are applied in the order
+/// they are added
+///
+/// ``` rust
+/// use reminisce::{Axis, Filter, Filters};
+/// let filters = Filters::new()
+///     .with(Axis::LeftX, Filter::Exponential(0.5))
+///     .with(Axis::LeftX, Filter::Hysteresis(200));
+/// assert_eq!(filters.get(Axis::LeftX), &[Filter::Exponential(0.5), Filter::Hysteresis(200)]);
+/// assert!(filters.get(Axis::LeftY).is_empty());
+/// ```
+pub struct Filters {
+	filters: Vec<Vec<Filter>>
+}
+
+impl Default for Filters {
+	fn default() -> Filters {
+		Filters::new()
+	}
+}
+
+impl Filters {
+	/// Make a set of filters that doesn't filter any axis
+	pub fn new() -> Filters {
+		Filters {
+			filters: vec![Vec::new(); NUM_AXES as usize]
+		}
+	}
+
+	/// Make a set of filters that applies a filter to every axis
+	pub fn all(filter: Filter) -> Filters {
+		Filters {
+			filters: vec![vec![filter]; NUM_AXES as usize]
+		}
+	}
+
+	/// Add a filter to an axis, after the filters it already has
+	pub fn with(mut self, axis: Axis, filter: Filter) -> Filters {
+		self.add(axis, filter);
+		self
+	}
+
+	/// Add a filter to an axis, after the filters it already has
+	pub fn add(&mut self, axis: Axis, filter: Filter) {
+		self.filters[axis as usize].push(filter)
+	}
+
+	/// Remove the filters of an axis
+	pub fn clear(&mut self, axis: Axis) {
+		self.filters[axis as usize].clear()
+	}
+
+	/// Get the filters of an axis
+	pub fn get(&self, axis: Axis) -> &[Filter] {
+		&self.filters[axis as usize]
+	}
+}
+
+/// A joystick with filters applied to its axes
+///
+/// The filters are applied to the axis events it emits. Events that don't change
+/// the value that was last emitted for the axis after it is filtered are dropped,
+/// so a jittery axis floods the event queue less. Filters only run when the
+/// joystick reports a new value, so a smoothed axis can stop short of where it
+/// was left until it moves again. If the joystick it wraps is stateful, the state
+/// of each axis is the value that was last emitted for it.
+///
+/// ``` rust
+/// use reminisce::{Axis, Event, Filter, Filters, Joystick, StatefulJoystick, VirtualJoystick};
+/// let js = VirtualJoystick::new(0, "Worn Pad");
+/// let controller = js.controller();
+/// let filters = Filters::new()
+///     .with(Axis::LeftX, Filter::Exponential(0.25))
+///     .with(Axis::LeftX, Filter::Hysteresis(500));
+/// let mut js = js.with_filters(filters);
+/// // A stick held at 10000 whose reading jitters by up to 400 either way
+/// let mut seed = 12345u32;
+/// for _ in 0..100 {
+///     seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+///     controller.move_axis(Axis::LeftX, 10000 + ((seed >> 16) % 801) as i16 - 400);
+/// }
+/// let mut events = 0;
+/// while let Some(event) = js.poll() {
+///     if let Event::AxisMoved(Axis::LeftX, value) = event {
+///         assert!(value > 8000 && value < 10500);
+///         events += 1;
+///     }
+/// }
+/// assert!(events < 10);
+/// let value = js.get_axis(Axis::LeftX).unwrap();
+/// assert!(value > 9000 && value < 10500);
+/// ```
+pub struct FilterJoystick<J> where J:Joystick {
+	joystick: J,
+	filters: Filters,
+	states: Vec<Vec<FilterState>>,
+	emitted: Vec<Option<i16>>
+}
+
+impl<J> FilterJoystick<J> where J:Joystick {
+	/// Start filtering the axes of a joystick
+	pub fn new(joystick: J, filters: Filters) -> FilterJoystick<J> {
+		FilterJoystick {
+			joystick: joystick,
+			filters: filters,
+			states: (0..NUM_AXES).map(|_| Vec::new()).collect(),
+			emitted: vec![None; NUM_AXES as usize]
+		}
+	}
+
+	/// Get the filters
+	pub fn get_filters(&self) -> &Filters {
+		&self.filters
+	}
+
+	/// Change the filters, which start again from the next value of each axis
+	pub fn set_filters(&mut self, filters: Filters) {
+		self.filters = filters;
+		for states in self.states.iter_mut() {
+			states.clear()
+		}
+	}
+
+	/// Get the joystick this wraps
+	pub fn get_ref(&self) -> &J {
+		&self.joystick
+	}
+
+	/// Get the joystick this wraps mutably
+	pub fn get_mut(&mut self) -> &mut J {
+		&mut self.joystick
+	}
+
+	/// Unwrap the joystick
+	pub fn into_inner(self) -> J {
+		self.joystick
+	}
+
+	/// Filter the value of an axis, or start the filters again from it if it is
+	/// the initial value
+	fn filter(&mut self, axis: Axis, value: i16, initial: bool, time: Duration) -> i16 {
+		let filters = self.filters.get(axis);
+		let states = &mut self.states[axis as usize];
+		let mut value = value as f32;
+		if initial || states.len() != filters.len() {
+			*states = filters.iter().map(|filter| FilterState::new(filter, value, time)).collect();
+		} else {
+			for (state, filter) in states.iter_mut().zip(filters.iter()) {
+				value = state.apply(filter, value, time);
+			}
+		}
+		value.max(MIN_AXIS_VALUE as f32).min(MAX_AXIS_VALUE as f32).round() as i16
+	}
+}
+
+impl<J> Joystick for FilterJoystick<J> where J:Joystick {
+	type WithState = FilterJoystick<<J as Joystick>::WithState>;
+	type NativeEvent = <J as Joystick>::NativeEvent;
+
+	fn open(index: u8) -> Result<FilterJoystick<J>, Error> {
+		Ok(FilterJoystick::new(try!(Joystick::open(index)), Filters::new()))
+	}
+	delegate_joystick!(methods);
+	fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+		loop {
+			let mut timed = match try!(self.joystick.try_poll_timed()) {
+				Some(timed) => timed,
+				None => return Ok(None)
+			};
+			let (axis, value, initial) = match timed.event {
+				Event::AxisMoved(axis, value) => (axis, value, false),
+				Event::InitialAxis(axis, value) => (axis, value, true),
+				_ => return Ok(Some(timed))
+			};
+			let value = self.filter(axis, value, initial, timed.time);
+			let emitted = &mut self.emitted[axis as usize];
+			if initial || *emitted != Some(value) {
+				*emitted = Some(value);
+				timed.event = if initial { Event::InitialAxis(axis, value) } else { Event::AxisMoved(axis, value) };
+				return Ok(Some(timed))
+			}
+		}
+	}
+	delegate_joystick!(mappings);
+	fn with_state(self) -> FilterJoystick<<J as Joystick>::WithState> {
+		FilterJoystick {
+			joystick: self.joystick.with_state(),
+			filters: self.filters,
+			states: self.states,
+			emitted: self.emitted
+		}
+	}
+}
+
+delegate_joystick!(impls FilterJoystick, Joystick);
+
+impl<J> StatefulJoystick for FilterJoystick<J> where J:StatefulJoystick {
+	/// This is the value that was last emitted for the axis, after it was filtered
+	fn get_axis(&self, axis: Axis) -> Option<i16> {
+		self.emitted[axis as usize].or_else(|| self.joystick.get_axis(axis))
+	}
+	fn get_button(&self, button: Button) -> Option<bool> {
+		self.joystick.get_button(button)
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.joystick.get_raw_axis(index)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.joystick.get_raw_button(index)
+	}
+	fn get_axis_time(&self, axis: Axis) -> Option<Duration> {
+		self.joystick.get_axis_time(axis)
+	}
+	fn get_button_time(&self, button: Button) -> Option<Duration> {
+		self.joystick.get_button_time(button)
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.joystick.get_hat(index)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
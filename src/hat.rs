@@ -0,0 +1,128 @@
+use {Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The directional pad buttons in the order their times are stored
+static DPAD: [Button; 4] = [Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight];
+
+/// A joystick that reports its first hat switch as the directional pad buttons too
+///
+/// Every `Event::HatMoved` for the first hat is followed by a `ButtonPressed` or
+/// `ButtonReleased` for each directional pad button it changed, and the state of
+/// those buttons is read from the hat.
+///
+/// ``` rust
+/// use reminisce::{scan, Button, Event, Joystick};
+/// for js in scan() {
+///     let mut js = js.dpad_from_hat();
+///     while let Some(event) = js.poll() {
+///         if event == Event::ButtonPressed(Button::DPadUp) {
+///             println!("Up!");
+///         }
+///     }
+/// }
+/// ```
+pub struct DPadFromHat<J> where J:Joystick {
+	joystick: J,
+	direction: HatDirection,
+	times: [Option<Duration>; 4],
+	pending: VecDeque<TimedEvent>
+}
+
+impl<J> DPadFromHat<J> where J:Joystick {
+	/// Start reporting the first hat of a joystick as the directional pad
+	pub fn new(joystick: J) -> DPadFromHat<J> {
+		DPadFromHat {
+			joystick: joystick,
+			direction: HatDirection::Centred,
+			times: [None; 4],
+			pending: VecDeque::with_capacity(4)
+		}
+	}
+
+	/// Get the joystick this wraps
+	pub fn get_ref(&self) -> &J {
+		&self.joystick
+	}
+
+	/// Unwrap the joystick
+	pub fn into_inner(self) -> J {
+		self.joystick
+	}
+}
+
+impl<J> Joystick for DPadFromHat<J> where J:Joystick {
+	type WithState = DPadFromHat<<J as Joystick>::WithState>;
+	type NativeEvent = <J as Joystick>::NativeEvent;
+
+	fn open(index: u8) -> Result<DPadFromHat<J>, Error> {
+		Ok(DPadFromHat::new(try!(Joystick::open(index))))
+	}
+	delegate_joystick!(methods);
+	fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+		if let Some(timed) = self.pending.pop_front() {
+			return Ok(Some(timed))
+		}
+		let timed = try!(self.joystick.try_poll_timed());
+		if let Some(TimedEvent { event: Event::HatMoved(0, direction), time, source }) = timed {
+			for (i, &button) in DPAD.iter().enumerate() {
+				let (was, is) = (self.direction.is_pressed(button), direction.is_pressed(button));
+				if was != is {
+					self.times[i] = Some(time);
+					self.pending.push_back(TimedEvent {
+						event: if is { Event::ButtonPressed(button) } else { Event::ButtonReleased(button) },
+						time: time,
+						source: source
+					})
+				}
+			}
+			self.direction = direction;
+		}
+		Ok(timed)
+	}
+	delegate_joystick!(mappings);
+	fn with_state(self) -> DPadFromHat<<J as Joystick>::WithState> {
+		DPadFromHat {
+			joystick: self.joystick.with_state(),
+			direction: self.direction,
+			times: self.times,
+			pending: self.pending
+		}
+	}
+}
+
+delegate_joystick!(impls DPadFromHat, Joystick);
+
+impl<J> StatefulJoystick for DPadFromHat<J> where J:StatefulJoystick {
+	fn get_axis(&self, axis: ::Axis) -> Option<i16> {
+		self.joystick.get_axis(axis)
+	}
+	/// The directional pad buttons are read from the first hat if the joystick has one
+	fn get_button(&self, button: Button) -> Option<bool> {
+		match self.joystick.get_hat(0) {
+			Some(direction) if DPAD.contains(&button) => Some(direction.is_pressed(button)),
+			_ => self.joystick.get_button(button)
+		}
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.joystick.get_raw_axis(index)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.joystick.get_raw_button(index)
+	}
+	fn get_axis_time(&self, axis: ::Axis) -> Option<Duration> {
+		self.joystick.get_axis_time(axis)
+	}
+	fn get_button_time(&self, button: Button) -> Option<Duration> {
+		match DPAD.iter().position(|&b| b == button) {
+			Some(i) if self.joystick.get_hat(0).is_some() => self.times[i],
+			_ => self.joystick.get_button_time(button)
+		}
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.joystick.get_hat(index)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use {scan, Axis, Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent, Waitable, Waiter, NUM_AXES, NUM_BUTTONS};
+
+/// How long the hub thread waits for input before checking if it should stop
+static STOP_CHECK_MS: u64 = 50;
+
+/// The most hats a joystick can have, which is how many Linux reports
+static MAX_HATS: u8 = 4;
+
+/// The state of a joystick on the hub thread, which is updated without locking
+struct SharedJoystick {
+	index: u8,
+	connected: AtomicBool,
+	axes: Vec<AtomicIsize>,
+	buttons: AtomicUsize,
+	raw_axes: Vec<AtomicIsize>,
+	raw_buttons: Vec<AtomicBool>,
+	/// The mask of the direction of each hat, if the joystick has it
+	hats: Vec<Option<AtomicUsize>>
+}
+
+impl SharedJoystick {
+	/// Start with the current state of a joystick
+	fn new<J>(js: &J) -> SharedJoystick where J:StatefulJoystick {
+		let buttons = (0..NUM_BUTTONS).filter_map(Button::from_index)
+			.filter(|&button| js.get_button(button).unwrap_or(false))
+			.fold(0, |buttons, button| buttons | 1 << button as usize);
+		SharedJoystick {
+			index: js.get_index(),
+			connected: AtomicBool::new(true),
+			axes: (0..NUM_AXES).map(|index| {
+				let value = Axis::from_index(index).and_then(|axis| js.get_axis(axis)).unwrap_or(0);
+				AtomicIsize::new(value as isize)
+			}).collect(),
+			buttons: AtomicUsize::new(buttons),
+			raw_axes: (0..js.get_num_axes()).map(|index| AtomicIsize::new(js.get_raw_axis(index).unwrap_or(0) as isize)).collect(),
+			raw_buttons: (0..js.get_num_buttons()).map(|index| AtomicBool::new(js.get_raw_button(index).unwrap_or(false))).collect(),
+			hats: (0..MAX_HATS).map(|hat| js.get_hat(hat).map(|direction| AtomicUsize::new(direction.to_mask() as usize))).collect()
+		}
+	}
+
+	/// Read the raw axes and buttons of a joystick again, because events for those
+	/// with an `Axis` or `Button` don't say which raw index changed
+	fn refresh_raw<J>(&self, js: &J) where J:StatefulJoystick {
+		for (index, axis) in self.raw_axes.iter().enumerate() {
+			if let Some(value) = js.get_raw_axis(index as u8) {
+				axis.store(value as isize, Ordering::Relaxed)
+			}
+		}
+		for (index, button) in self.raw_buttons.iter().enumerate() {
+			if let Some(pressed) = js.get_raw_button(index as u8) {
+				button.store(pressed, Ordering::Relaxed)
+			}
+		}
+	}
+
+	/// Record the change in state from an event
+	fn update(&self, event: Event) {
+		match event {
+			Event::InitialAxis(axis, value) | Event::AxisMoved(axis, value) =>
+				self.axes[axis as usize].store(value as isize, Ordering::Relaxed),
+			Event::InitialButton(button, true) | Event::ButtonPressed(button) => {
+				self.buttons.fetch_or(1 << button as usize, Ordering::Relaxed);
+			},
+			Event::InitialButton(button, false) | Event::ButtonReleased(button) => {
+				self.buttons.fetch_and(!(1 << button as usize), Ordering::Relaxed);
+			},
+			Event::RawAxisMoved(index, value) => if let Some(axis) = self.raw_axes.get(index as usize) {
+				axis.store(value as isize, Ordering::Relaxed)
+			},
+			Event::RawButton(index, pressed) => if let Some(button) = self.raw_buttons.get(index as usize) {
+				button.store(pressed, Ordering::Relaxed)
+			},
+			Event::HatMoved(hat, direction) => if let Some(&Some(ref mask)) = self.hats.get(hat as usize) {
+				mask.store(direction.to_mask() as usize, Ordering::Relaxed)
+			}
+		}
+	}
+}
+
+/// The current state of every joystick a hub owns, which can be read from any thread
+///
+/// This is updated by the hub thread with atomics, so reading it never blocks.
+/// Each value is up to date on its own, but different values can be read from
+/// either side of an event.
+pub struct SharedState {
+	joysticks: Vec<SharedJoystick>
+}
+
+impl SharedState {
+	/// Get the joystick with the given index
+	fn get(&self, index: u8) -> Option<&SharedJoystick> {
+		self.joysticks.iter().find(|js| js.index == index)
+	}
+
+	/// Get the indices of the joysticks the hub owns
+	pub fn indices(&self) -> Vec<u8> {
+		self.joysticks.iter().map(|js| js.index).collect()
+	}
+
+	/// Check if the joystick with the given index is still connected
+	pub fn is_connected(&self, index: u8) -> bool {
+		self.get(index).map(|js| js.connected.load(Ordering::Relaxed)).unwrap_or(false)
+	}
+
+	/// Get the last value of an axis of the joystick with the given index
+	pub fn get_axis(&self, index: u8, axis: Axis) -> Option<i16> {
+		self.get(index).map(|js| js.axes[axis as usize].load(Ordering::Relaxed) as i16)
+	}
+
+	/// Check if a button of the joystick with the given index is pressed
+	pub fn get_button(&self, index: u8, button: Button) -> Option<bool> {
+		self.get(index).map(|js| js.buttons.load(Ordering::Relaxed) & (1 << button as usize) != 0)
+	}
+
+	/// Get the last value of an axis of the joystick with the given index, from the
+	/// index the backend reports it with
+	///
+	/// This includes axes that have an `Axis`, which are kept up to date as well.
+	pub fn get_raw_axis(&self, index: u8, axis: u8) -> Option<i16> {
+		self.get(index).and_then(|js| js.raw_axes.get(axis as usize)).map(|value| value.load(Ordering::Relaxed) as i16)
+	}
+
+	/// Check if a button of the joystick with the given index is pressed, from the
+	/// index the backend reports it with
+	///
+	/// This includes buttons that have a `Button`, which are kept up to date as well.
+	pub fn get_raw_button(&self, index: u8, button: u8) -> Option<bool> {
+		self.get(index).and_then(|js| js.raw_buttons.get(button as usize)).map(|pressed| pressed.load(Ordering::Relaxed))
+	}
+
+	/// Get the direction a hat of the joystick with the given index is pushed in
+	pub fn get_hat(&self, index: u8, hat: u8) -> Option<HatDirection> {
+		match self.get(index).and_then(|js| js.hats.get(hat as usize)) {
+			Some(&Some(ref mask)) => Some(HatDirection::from_mask(mask.load(Ordering::Relaxed) as u8)),
+			_ => None
+		}
+	}
+}
+
+/// A dedicated thread that owns joysticks and sends their events through a channel
+///
+/// The joysticks are opened on the hub thread, so they don't need to be `Send`.
+/// Each event is tagged with the index of the joystick that emitted it and the
+/// time the backend gave it, and the current state of every joystick can be read
+/// from any thread through `get_state`, starting from the state each joystick
+/// was in when it was opened. The thread sleeps until a joystick has input, and
+/// stops when every joystick is unplugged or the hub is dropped.
+///
+/// ``` rust
+/// use reminisce::{Axis, Event};
+/// use reminisce::hub::Hub;
+/// let hub = Hub::new();
+/// let state = hub.get_state();
+/// for timed in hub.try_iter() {
+///     if let Event::ButtonPressed(button) = timed.event {
+///         println!("Joystick #{} pressed {:?} at {:?}", timed.source, button, timed.time);
+///     }
+/// }
+/// for index in state.indices() {
+///     println!("Joystick #{} is at {:?}", index, state.get_axis(index, Axis::LeftX));
+/// }
+/// ```
+pub struct Hub {
+	events: Receiver<TimedEvent>,
+	state: Arc<SharedState>,
+	running: Arc<AtomicBool>,
+	thread: Option<JoinHandle<()>>
+}
+
+impl Hub {
+	/// Spawn a hub that owns every joystick plugged in
+	pub fn new() -> Hub {
+		Hub::spawn(|| scan().into_iter().map(|js| js.with_state()).collect())
+	}
+
+	/// Spawn a hub that owns the joysticks `open` returns, which is called on the hub thread
+	///
+	/// This blocks until the joysticks have been opened.
+	pub fn spawn<J, F>(open: F) -> Hub where J:Waitable+StatefulJoystick+'static, F:FnOnce() -> Vec<J>+Send+'static {
+		let (event_sender, events) = mpsc::channel();
+		let (state_sender, state_receiver) = mpsc::channel();
+		let running = Arc::new(AtomicBool::new(true));
+		let thread_running = running.clone();
+		let thread = thread::spawn(move || {
+			let mut joysticks = open();
+			let state = Arc::new(SharedState {
+				joysticks: joysticks.iter().map(SharedJoystick::new).collect()
+			});
+			if state_sender.send(state.clone()).is_err() {
+				return
+			}
+			// The position in `state` of each joystick that is still connected
+			let mut slots: Vec<usize> = (0..joysticks.len()).collect();
+			let timeout = Some(Duration::from_millis(STOP_CHECK_MS));
+			let mut waiter = Waiter::new();
+			while !joysticks.is_empty() && thread_running.load(Ordering::Relaxed) {
+				let failed = match waiter.wait_timed(&mut joysticks, timeout) {
+					Some((index, Ok(timed))) => {
+						let shared = &state.joysticks[slots[index]];
+						shared.update(timed.event);
+						match timed.event {
+							Event::RawAxisMoved(..) | Event::RawButton(..) => (),
+							_ => shared.refresh_raw(&joysticks[index])
+						}
+						if event_sender.send(timed).is_err() {
+							return
+						}
+						None
+					},
+					// Unknown events are skipped, but any other error would keep failing
+					Some((_, Err(Error::UnknownEvent(_, _)))) => None,
+					Some((index, Err(_))) => Some(index),
+					None => None
+				};
+				// Disconnected and failed joysticks are closed so they don't wake the thread up again
+				for index in (0..joysticks.len()).rev() {
+					if failed == Some(index) || !joysticks[index].is_connected() {
+						state.joysticks[slots[index]].connected.store(false, Ordering::Relaxed);
+						joysticks.remove(index);
+						slots.remove(index);
+					}
+				}
+			}
+		});
+		let state = state_receiver.recv().unwrap_or_else(|_| Arc::new(SharedState { joysticks: Vec::new() }));
+		Hub {
+			events: events,
+			state: state,
+			running: running,
+			thread: Some(thread)
+		}
+	}
+
+	/// Get the current state of the joysticks, which can be sent to other threads
+	pub fn get_state(&self) -> Arc<SharedState> {
+		self.state.clone()
+	}
+
+	/// Get the receiver the hub thread sends events through
+	pub fn get_receiver(&self) -> &Receiver<TimedEvent> {
+		&self.events
+	}
+
+	/// Get the next event without blocking, if there is one
+	pub fn poll(&self) -> Option<TimedEvent> {
+		self.events.try_recv().ok()
+	}
+
+	/// Block until there is an event, or return none if the hub thread has stopped
+	pub fn recv(&self) -> Option<TimedEvent> {
+		self.events.recv().ok()
+	}
+
+	/// Iterate through the events that have been received without blocking
+	pub fn try_iter(&self) -> HubPoller {
+		HubPoller {
+			hub: self
+		}
+	}
+}
+
+impl Drop for Hub {
+	/// Stop the hub thread and wait for it to close the joysticks
+	fn drop(&mut self) {
+		self.running.store(false, Ordering::Relaxed);
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// An iterator over the events a hub has received
+pub struct HubPoller<'a> {
+	hub: &'a Hub
+}
+
+impl<'a> Iterator for HubPoller<'a> {
+	type Item = TimedEvent;
+	/// This calls the `hub.poll()` method to get the next event
+	fn next(&mut self) -> Option<TimedEvent> {
+		self.hub.poll()
+	}
+}
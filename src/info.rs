@@ -0,0 +1,181 @@
+use std::fmt;
+use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use Error;
+
+/// The bus type of a USB device
+pub const BUS_USB: u16 = 0x03;
+/// The bus type of a Bluetooth device
+pub const BUS_BLUETOOTH: u16 = 0x05;
+/// The bus type of a virtual device
+pub const BUS_VIRTUAL: u16 = 0x06;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A 128-bit identifier of a kind of joystick, computed the same way SDL does
+///
+/// Two joysticks of the same model have the same GUID, so this can be used
+/// to look up mappings for a joystick.
+pub struct Guid(pub [u8; 16]);
+
+impl Guid {
+	/// Compute the GUID of a device from its bus type, vendor id, product id and version
+	///
+	/// This lays the ids out as little-endian 16-bit numbers, each followed by
+	/// two zero bytes.
+	///
+	/// ``` rust
+	/// use reminisce::{BUS_USB, Guid};
+	/// let guid = Guid::from_ids(BUS_USB, 0x045e, 0x028e, 0x0114);
+	/// assert_eq!(guid.to_string(), "030000005e0400008e02000014010000");
+	/// ```
+	pub fn from_ids(bus: u16, vendor: u16, product: u16, version: u16) -> Guid {
+		let mut bytes = [0; 16];
+		for (i, &id) in [bus, vendor, product, version].iter().enumerate() {
+			bytes[i * 4] = id as u8;
+			bytes[i * 4 + 1] = (id >> 8) as u8;
+		}
+		Guid(bytes)
+	}
+
+	/// Compute the GUID of a device that doesn't report a vendor or product id
+	///
+	/// This is the bus type followed by the first 12 bytes of its name.
+	pub fn from_name(bus: u16, name: &str) -> Guid {
+		let mut bytes = [0; 16];
+		bytes[0] = bus as u8;
+		bytes[1] = (bus >> 8) as u8;
+		for (byte, &c) in bytes[4..].iter_mut().zip(name.as_bytes()) {
+			*byte = c;
+		}
+		Guid(bytes)
+	}
+
+	/// Parse a GUID from 32 hexadecimal digits, like those in SDL's mapping strings
+	pub fn from_hex(text: &str) -> Option<Guid> {
+		if text.len() != 32 || !text.chars().all(|c| c.is_digit(16)) {
+			return None
+		}
+		let mut bytes = [0; 16];
+		for (i, byte) in bytes.iter_mut().enumerate() {
+			*byte = u8::from_str_radix(&text[i * 2 .. i * 2 + 2], 16).unwrap();
+		}
+		Some(Guid(bytes))
+	}
+}
+
+impl fmt::Display for Guid {
+	/// This writes the GUID as 32 lowercase hexadecimal digits
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		for byte in self.0.iter() {
+			try!(write!(fmt, "{:02x}", byte));
+		}
+		Ok(())
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Information identifying a joystick
+pub struct DeviceInfo {
+	/// The name of the device
+	pub name: String,
+	/// The bus type, such as `BUS_USB`, or 0 if it isn't known
+	pub bus: u16,
+	/// The vendor id, or 0 if it isn't known
+	pub vendor: u16,
+	/// The product id, or 0 if it isn't known
+	pub product: u16,
+	/// The version of the product, or 0 if it isn't known
+	pub version: u16,
+	/// The path to the device in sysfs
+	pub path: Option<PathBuf>,
+	/// The unique identifier of the device, like a serial number or Bluetooth address
+	///
+	/// This is how two joysticks of the same model can be told apart.
+	pub unique: Option<String>,
+	/// The physical location of the device, like the USB port it is plugged into
+	pub phys: Option<String>,
+	/// The GUID of the device
+	pub guid: Guid
+}
+
+impl DeviceInfo {
+	/// Make the information of a device that is only known by its name
+	pub fn from_name(name: String) -> DeviceInfo {
+		DeviceInfo {
+			guid: Guid::from_name(0, &name),
+			name: name,
+			bus: 0,
+			vendor: 0,
+			product: 0,
+			version: 0,
+			path: None,
+			unique: None,
+			phys: None
+		}
+	}
+
+	/// Read the information of an input device from its directory in sysfs, like
+	/// `/sys/class/input/js0/device`
+	///
+	/// ``` rust
+	/// use std::fs;
+	/// use reminisce::DeviceInfo;
+	/// let dir = std::env::temp_dir().join("reminisce-info-doc");
+	/// fs::create_dir_all(dir.join("id")).unwrap();
+	/// fs::write(dir.join("name"), "Microsoft X-Box 360 pad\n").unwrap();
+	/// for &(file, id) in &[("bustype", "0003"), ("vendor", "045e"), ("product", "028e"), ("version", "0114")] {
+	///     fs::write(dir.join("id").join(file), id).unwrap();
+	/// }
+	/// let info = DeviceInfo::from_sysfs(&dir).unwrap();
+	/// assert_eq!(info.name, "Microsoft X-Box 360 pad");
+	/// assert_eq!((info.vendor, info.product), (0x045e, 0x028e));
+	/// assert_eq!(info.guid.to_string(), "030000005e0400008e02000014010000");
+	/// assert_eq!(info.unique, None);
+	/// fs::remove_dir_all(&dir).unwrap();
+	/// ```
+	#[cfg(target_os = "linux")]
+	pub fn from_sysfs<P>(path: P) -> Result<DeviceInfo, Error> where P:AsRef<Path> {
+		use std::fs::File;
+		use std::io::Read;
+		let path = path.as_ref();
+		let read = |name: &str| -> Result<Option<String>, Error> {
+			let mut text = String::new();
+			match File::open(path.join(name)) {
+				Ok(mut file) => {
+					try!(file.read_to_string(&mut text));
+					let text = text.trim();
+					Ok(if text.is_empty() { None } else { Some(text.to_string()) })
+				},
+				Err(_) => Ok(None)
+			}
+		};
+		let read_id = |name: &str| -> Result<u16, Error> {
+			match try!(read(name)) {
+				Some(text) => u16::from_str_radix(&text, 16).map_err(|_| Error::Backend(format!("invalid {} in sysfs: {}", name, text))),
+				None => Ok(0)
+			}
+		};
+		let name = match try!(read("name")) {
+			Some(name) => name,
+			None => return Err(Error::NotFound)
+		};
+		let (bus, vendor, product, version) = (try!(read_id("id/bustype")), try!(read_id("id/vendor")), try!(read_id("id/product")), try!(read_id("id/version")));
+		Ok(DeviceInfo {
+			guid: if vendor != 0 && product != 0 {
+				Guid::from_ids(bus, vendor, product, version)
+			} else {
+				Guid::from_name(bus, &name)
+			},
+			name: name,
+			bus: bus,
+			vendor: vendor,
+			product: product,
+			version: version,
+			path: path.canonicalize().ok().or_else(|| Some(path.to_path_buf())),
+			unique: try!(read("uniq")),
+			phys: try!(read("phys"))
+		})
+	}
+}
@@ -0,0 +1,720 @@
+use libc::{c_char, c_ulong, c_int, c_uint, O_RDONLY, read};
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+use evdev::{self, ABS_HAT0X};
+use {Axis, Button, Error, HatDirection, Joystick, Rumble};
+
+pub mod uinput;
+
+static JSIOCGAXES: c_uint = 2147576337;
+static JSIOCGBUTTONS: c_uint = 2147576338;
+static JSIOCGID: c_uint = 2151705107;
+static JSIOCGID_LEN: usize = 64;
+static JSIOCGAXMAP: c_uint = 2151705138;
+static JSIOCGAXMAP_LEN: usize = 64;
+static JSIOCGBTNMAP: c_uint = 2214619700;
+static JSIOCGBTNMAP_LEN: usize = 512;
+static JSIOCSCORR: c_uint = 1076128289;
+static JSIOCGCORR: c_uint = 2149870114;
+const JS_CORR_NONE: u16 = 0;
+const JS_CORR_BROKEN: u16 = 1;
+const JS_EVENT_INIT: u8 = 0x80;
+
+extern {
+	fn open(path: *const c_char, oflag: c_int) -> c_int;
+	fn close(fd: c_int) -> c_int;
+	fn ioctl(fd: c_uint, op: c_uint, result: *mut c_char) -> c_int;
+}
+
+/// Get the index of a joystick from its device file name, like `js0`
+pub fn device_index(name: &str) -> Option<u8> {
+	if name.starts_with("js") {
+		name[2..].parse().ok()
+	} else {
+		None
+	}
+}
+
+/// Scan for joysticks
+pub fn scan() -> Vec<NativeJoystick> {
+	use std::fs;
+	// So many ifs...
+	let mut joysticks = Vec::with_capacity(4);
+	if let Ok(entries) = fs::walk_dir("/dev/input/") {
+		for entry in entries {
+			if let Ok(entry) = entry {
+				let path = entry.path();
+				if let Some(name) = path.file_name() {
+					if let Some(name) = name.to_str() {
+						if let Some(index) = device_index(name) {
+							if let Ok(js) = Joystick::open(index) {
+								joysticks.push(js)
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+	joysticks
+}
+
+/// Represents a system joystick
+///
+/// The axes and buttons are translated into an `Axis` or `Button` using the
+/// `ABS_*` and `BTN_*` codes the kernel maps them to, so `Button::A` is the
+/// south face button. Hat switches are reported as `Event::HatMoved`.
+pub struct NativeJoystick {
+	index: u8,
+	fd: c_int,
+	connected: bool,
+	pending: VecDeque<LinuxEvent>,
+	report_initial: bool,
+	axis_codes: Vec<u16>,
+	button_codes: Vec<u16>,
+	axis_values: Vec<i16>,
+	/// The index of the event device of the same joystick, if it can rumble
+	event_index: Option<u8>,
+	/// The event device, which is only opened the first time the joystick rumbles
+	event_device: Option<evdev::NativeJoystick>
+}
+
+impl NativeJoystick {
+	/// Set whether the synthetic events the kernel sends with the state of every axis
+	/// and button when the joystick is opened should be returned
+	///
+	/// These are returned as `Event::InitialAxis` and `Event::InitialButton`, so they
+	/// can't be mistaken for the user pressing a button. They aren't reported by default.
+	pub fn report_initial_state(&mut self, report: bool) {
+		self.report_initial = report;
+	}
+
+	/// Get the `ABS_*` codes the kernel maps each axis to, in the order of their numbers
+	pub fn get_axis_codes(&self) -> &[u16] {
+		&self.axis_codes
+	}
+
+	/// Get the `BTN_*` codes the kernel maps each button to, in the order of their numbers
+	pub fn get_button_codes(&self) -> &[u16] {
+		&self.button_codes
+	}
+
+	/// Read the correction the kernel applies to each axis
+	pub fn get_calibration(&self) -> Result<Calibration, Error> {
+		let mut axes = vec![Correction::none(); self.get_num_axes() as usize];
+		if unsafe { ioctl(self.fd as u32, JSIOCGCORR, axes.as_mut_ptr() as *mut c_char) } == -1 {
+			Err(Error::last_os_error())
+		} else {
+			Ok(Calibration {
+				axes: axes
+			})
+		}
+	}
+
+	/// Replace the correction the kernel applies to each axis
+	///
+	/// This affects every process reading the joystick until it is unplugged.
+	/// The calibration must have a correction for every axis.
+	pub fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), Error> {
+		if calibration.axes.len() != self.get_num_axes() as usize {
+			return Err(Error::Backend(format!("calibration has {} axes but the joystick has {}", calibration.axes.len(), self.get_num_axes())))
+		}
+		let mut axes = calibration.axes.clone();
+		if unsafe { ioctl(self.fd as u32, JSIOCSCORR, axes.as_mut_ptr() as *mut c_char) } == -1 {
+			Err(Error::last_os_error())
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Get the number of the first axis that represents `axis`
+	fn axis_number(&self, axis: Axis) -> Option<usize> {
+		self.axis_codes.iter().position(|&code| evdev::axis_from_code(code) == Some(axis))
+	}
+
+	/// Get the number of the first button that represents `button`
+	fn button_number(&self, button: Button) -> Option<usize> {
+		self.button_codes.iter().position(|&code| evdev::button_from_code(code) == Some(button))
+	}
+
+	/// Get the numbers of the horizontal and vertical axes of a hat
+	fn hat_numbers(&self, hat: u8) -> (Option<usize>, Option<usize>) {
+		let code = ABS_HAT0X + hat as u16 * 2;
+		(self.axis_codes.iter().position(|&c| c == code), self.axis_codes.iter().position(|&c| c == code + 1))
+	}
+
+	/// Get the direction a hat is pushed in from the values of its axes
+	fn hat_direction(&self, hat: u8, axes: &[i16]) -> Option<HatDirection> {
+		match self.hat_numbers(hat) {
+			(None, None) => None,
+			(x, y) => Some(HatDirection::from_axes(x.map(|n| axes[n]).unwrap_or(0), y.map(|n| axes[n]).unwrap_or(0)))
+		}
+	}
+
+	/// Find the event device of the same joystick, which is used for rumble because
+	/// the joystick interface doesn't support it
+	///
+	/// The event device is only kept if it supports `FF_RUMBLE` effects.
+	fn find_event_device(&mut self) {
+		let path = format!("/sys/class/input/js{}/device", self.index);
+		if let Ok(entries) = ::std::fs::read_dir(path) {
+			for entry in entries {
+				let index = entry.ok().and_then(|entry| entry.file_name().to_str().and_then(evdev::device_index));
+				if let Some(index) = index {
+					if evdev::device_supports_rumble(index) {
+						self.event_index = Some(index)
+					}
+					return
+				}
+			}
+		}
+	}
+
+	/// Read the axis and button maps from the kernel
+	///
+	/// If the kernel doesn't support this, the numbers are assumed to be in the
+	/// same order as `Axis` and `Button`.
+	fn read_maps(&mut self) {
+		let (num_axes, num_buttons) = (self.get_num_axes() as usize, self.get_num_buttons() as usize);
+		let mut axis_map = [0u8; 64];
+		let mut button_map = [0u16; 512];
+		unsafe {
+			if ioctl(self.fd as u32, JSIOCGAXMAP, axis_map.as_mut_ptr() as *mut c_char) >= 0 {
+				self.axis_codes = axis_map[..num_axes.min(JSIOCGAXMAP_LEN)].iter().map(|&code| code as u16).collect();
+			} else {
+				self.axis_codes = (0..num_axes).map(|n| Axis::from_index(n as u8).map(evdev::axis_code).unwrap_or(!0)).collect();
+			}
+			if ioctl(self.fd as u32, JSIOCGBTNMAP, button_map.as_mut_ptr() as *mut c_char) >= 0 {
+				self.button_codes = button_map[..num_buttons.min(JSIOCGBTNMAP_LEN)].to_vec();
+			} else {
+				self.button_codes = (0..num_buttons).map(|n| Button::from_index(n as u8).map(evdev::button_code).unwrap_or(!0)).collect();
+			}
+		}
+		self.axis_values = vec![0; num_axes];
+	}
+
+	/// Read a single event from the interface in non-blocking mode
+	fn read_event(&mut self) -> Result<Option<LinuxEvent>, Error> {
+		unsafe {
+			let mut event:JsEvent = mem::uninitialized();
+			let event_size = mem::size_of::<JsEvent>() as c_ulong;
+			if read(self.fd, mem::transmute(&mut event), event_size) == -1 {
+				let err = ::std::io::Error::last_os_error();
+				match err.raw_os_error() {
+					Some(11) => Ok(None),
+					Some(19) => {
+						self.connected = false;
+						Err(Error::Disconnected)
+					},
+					_ => Err(err.into())
+				}
+			} else {
+				let number = event.number as usize;
+				let (kind, code, hat) = match event._type & !JS_EVENT_INIT {
+					1 => (JsKind::Button, self.button_codes.get(number).cloned().unwrap_or(!0), None),
+					2 if number < self.axis_values.len() => {
+						self.axis_values[number] = event.value;
+						let code = self.axis_codes[number];
+						(JsKind::Axis, code, evdev::hat_from_code(code).and_then(|(hat, _)| {
+							self.hat_direction(hat, &self.axis_values).map(|direction| (hat, direction))
+						}))
+					},
+					2 => (JsKind::Axis, !0, None),
+					_ => return Err(Error::UnknownEvent(event._type as u16, event.value as i32))
+				};
+				Ok(Some(LinuxEvent {
+					time: event.time,
+					value: event.value,
+					kind: kind,
+					initial: event._type & JS_EVENT_INIT != 0,
+					number: event.number,
+					code: code,
+					hat: hat
+				}))
+			}
+		}
+	}
+}
+
+impl ::Joystick for NativeJoystick {
+	type WithState = StatefulNativeJoystick;
+	type NativeEvent = LinuxEvent;
+	/// This tries to open the interface `/dev/input/js...` and will return the
+	/// OS-level error if it fails to open this
+	fn open(index: u8) -> Result<NativeJoystick, Error> {
+		let path = format!("/dev/input/js{}", index);
+		unsafe {
+			let c_path = CString::new(path.as_bytes()).unwrap();
+			let fd =  open(c_path.as_ptr(), O_RDONLY | 0x800);
+			if fd == -1 {
+				Err(Error::last_os_error())
+			} else {
+				let mut js = NativeJoystick {
+					index: index,
+					fd: fd,
+					connected: true,
+					pending: VecDeque::with_capacity(32),
+					report_initial: false,
+					axis_codes: Vec::new(),
+					button_codes: Vec::new(),
+					axis_values: Vec::new(),
+					event_index: None,
+					event_device: None
+				};
+				js.read_maps();
+				js.find_event_device();
+				// The kernel queues the initial state as soon as the joystick is opened
+				loop {
+					match js.read_event() {
+						Ok(Some(event)) => js.pending.push_back(event),
+						Err(Error::UnknownEvent(..)) => (),
+						_ => break
+					}
+				}
+				Ok(js)
+			}
+		}
+	}
+	/// This reads from the interface in non-blocking mode
+	///
+	/// If the joystick has been unplugged, this returns `Error::Disconnected`
+	/// and `is_connected` will return false from then on.
+	fn try_poll_native(&mut self) -> Result<Option<LinuxEvent>, Error> {
+		loop {
+			let event = match self.pending.pop_front() {
+				Some(event) => event,
+				None => match try!(self.read_event()) {
+					Some(event) => event,
+					None => return Ok(None)
+				}
+			};
+			if self.report_initial || !event.initial {
+				return Ok(Some(event))
+			}
+		}
+	}
+	/// The kernel timestamps events in milliseconds
+	fn get_event_time(event: &LinuxEvent) -> Duration {
+		Duration::from_millis(event.time as u64)
+	}
+	fn is_connected(&self) -> bool {
+		self.connected
+	}
+	fn get_num_axes(&self) -> u8 {
+		unsafe {
+			let mut num_axes: c_char = mem::uninitialized();
+			ioctl(self.fd as u32, JSIOCGAXES, &mut num_axes as *mut i8);
+			num_axes as u8
+		}
+	}
+	fn get_num_buttons(&self) -> u8 {
+		unsafe {
+			let mut num_buttons: c_char = mem::uninitialized();
+			ioctl(self.fd as u32, JSIOCGBUTTONS, &mut num_buttons as *mut i8);
+			num_buttons as u8
+		}
+	}
+	fn get_id(&self) -> Cow<str> {
+		unsafe {
+			let text = String::with_capacity(JSIOCGID_LEN);
+			ioctl(self.fd as u32, JSIOCGID, text.as_ptr() as *mut i8);
+			let mut new_text = String::from_raw_parts(text.as_ptr() as *mut u8, JSIOCGID_LEN, JSIOCGID_LEN);
+			let length = CStr::from_ptr(text.as_ptr() as *const i8).to_bytes().len();
+			mem::forget(text);
+			new_text.truncate(length);
+			new_text.shrink_to_fit();
+			new_text.into()
+		}
+	}
+	fn get_index(&self) -> u8 {
+		self.index
+	}
+	/// This reads the information from `/sys/class/input/js{index}/device`
+	fn get_info(&self) -> ::DeviceInfo {
+		let path = format!("/sys/class/input/js{}/device", self.index);
+		::DeviceInfo::from_sysfs(path).unwrap_or_else(|_| ::DeviceInfo::from_name(self.get_id().into_owned()))
+	}
+	/// This reads the power supply of the device in `/sys/class/power_supply`
+	fn get_battery(&self) -> Option<f32> {
+		let path = format!("/sys/class/input/js{}/device", self.index);
+		::battery::PowerSupply::find(path).and_then(|supply| supply.get_level())
+	}
+	/// This reads the power supply of the device in `/sys/class/power_supply`
+	fn get_battery_state(&self) -> Option<::BatteryState> {
+		::battery::read_state(format!("/sys/class/input/js{}/device", self.index))
+	}
+	/// The kernel numbers the buttons in the same order as SDL
+	#[cfg(feature = "mappings")]
+	fn get_mapping_button(&self, number: u8) -> Option<::mapper::Source> {
+		self.button_codes.get(number as usize).map(|&code| match evdev::button_from_code(code) {
+			Some(button) => ::mapper::Source::Button(button),
+			None => ::mapper::Source::RawButton(number)
+		})
+	}
+	/// SDL skips the axes of hats when it numbers the axes, which the kernel doesn't
+	#[cfg(feature = "mappings")]
+	fn get_mapping_axis(&self, number: u8) -> Option<::mapper::Source> {
+		let full = ::mapper::AxisRange::Full;
+		let mut axes = self.axis_codes.iter().enumerate().filter(|&(_, &code)| evdev::hat_from_code(code).is_none());
+		axes.nth(number as usize).map(|(index, &code)| match evdev::axis_from_code(code) {
+			Some(axis) => ::mapper::Source::Axis(axis, full),
+			None => ::mapper::Source::RawAxis(index as u8, full)
+		})
+	}
+	fn with_state(self) -> StatefulNativeJoystick {
+		StatefulNativeJoystick::wrap(self)
+	}
+}
+
+impl AsRawFd for NativeJoystick {
+	fn as_raw_fd(&self) -> RawFd {
+		self.fd
+	}
+}
+
+impl Drop for NativeJoystick {
+	/// Close the joystick's file descriptor
+	///
+	/// Any error is ignored because the descriptor is released either way.
+	fn drop(&mut self) {
+		unsafe {
+			close(self.fd);
+		}
+	}
+}
+
+impl Rumble for NativeJoystick {
+	/// This checks if the event device of the joystick supports `FF_RUMBLE` effects
+	fn supports_rumble(&self) -> bool {
+		self.event_index.is_some()
+	}
+	/// This uses the event device of the joystick, which is opened the first time
+	/// this is called and needs write permission to `/dev/input/event...`
+	///
+	/// If the event device can't be opened this returns the OS-level error, and it
+	/// is opened again the next time this is called. Without write permission the
+	/// device is opened for reading, and uploading the effect fails instead.
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+		if self.event_device.is_none() {
+			match self.event_index {
+				Some(index) => self.event_device = Some(try!(Joystick::open(index))),
+				None => return Err(Error::Unsupported)
+			}
+		}
+		self.event_device.as_mut().unwrap().rumble(strong, weak, duration)
+	}
+}
+
+/// The default joystick that tracks its state
+///
+/// This tracks every axis and button the joystick reports, including those that
+/// don't have an `Axis` or `Button`. The state is seeded from the initial state the
+/// kernel reports, so buttons that are already held and sticks that rest off-centre
+/// are reported correctly.
+pub struct StatefulNativeJoystick {
+	js: NativeJoystick,
+	axes: Vec<i16>,
+	buttons: Vec<bool>,
+	axis_times: Vec<Option<Duration>>,
+	button_times: Vec<Option<Duration>>
+}
+impl StatefulNativeJoystick {
+	/// Wrap a joystick
+	pub fn wrap(js: NativeJoystick) -> StatefulNativeJoystick {
+		let (num_axes, num_buttons) = (js.get_num_axes() as usize, js.get_num_buttons() as usize);
+		let mut axes = vec![0; num_axes];
+		let mut buttons = vec![false; num_buttons];
+		for event in js.pending.iter().filter(|e| e.initial) {
+			match event.kind {
+				JsKind::Button => if let Some(button) = buttons.get_mut(event.number as usize) {
+					*button = event.value != 0
+				},
+				JsKind::Axis => if let Some(axis) = axes.get_mut(event.number as usize) {
+					*axis = event.value
+				}
+			}
+		}
+		StatefulNativeJoystick {
+			axes: axes,
+			buttons: buttons,
+			axis_times: vec![None; num_axes],
+			button_times: vec![None; num_buttons],
+			js: js
+		}
+	}
+}
+impl ::Joystick for StatefulNativeJoystick {
+	type WithState = StatefulNativeJoystick;
+	type NativeEvent = LinuxEvent;
+
+	fn open(index: u8) -> Result<StatefulNativeJoystick, Error> {
+		::Joystick::open(index).map(|js| StatefulNativeJoystick::wrap(js))
+	}
+	fn is_connected(&self) -> bool {
+		self.js.is_connected()
+	}
+	fn get_id(&self) -> Cow<str> {
+		self.js.get_id()
+	}
+	fn get_info(&self) -> ::DeviceInfo {
+		self.js.get_info()
+	}
+	fn get_index(&self) -> u8 {
+		self.js.get_index()
+	}
+	fn get_num_axes(&self) -> u8 {
+		self.js.get_num_axes()
+	}
+	fn get_num_buttons(&self) -> u8 {
+		self.js.get_num_buttons()
+	}
+	fn get_battery(&self) -> Option<f32> {
+		self.js.get_battery()
+	}
+	fn get_battery_state(&self) -> Option<::BatteryState> {
+		self.js.get_battery_state()
+	}
+	fn try_poll_native(&mut self) -> Result<Option<LinuxEvent>, Error> {
+		let event = try!(self.js.try_poll_native());
+		if let Some(ref event) = event {
+			let time = Some(NativeJoystick::get_event_time(event));
+			let number = event.number as usize;
+			match event.kind {
+				JsKind::Button if number < self.buttons.len() => {
+					self.buttons[number] = event.value != 0;
+					self.button_times[number] = time;
+				},
+				JsKind::Axis if number < self.axes.len() => {
+					self.axes[number] = event.value;
+					self.axis_times[number] = time;
+				},
+				_ => ()
+			}
+		}
+		Ok(event)
+	}
+	fn get_event_time(event: &LinuxEvent) -> Duration {
+		NativeJoystick::get_event_time(event)
+	}
+	#[cfg(feature = "mappings")]
+	fn get_mapping_button(&self, number: u8) -> Option<::mapper::Source> {
+		self.js.get_mapping_button(number)
+	}
+	#[cfg(feature = "mappings")]
+	fn get_mapping_axis(&self, number: u8) -> Option<::mapper::Source> {
+		self.js.get_mapping_axis(number)
+	}
+	fn with_state(self) -> StatefulNativeJoystick {
+		self
+	}
+}
+impl AsRawFd for StatefulNativeJoystick {
+	fn as_raw_fd(&self) -> RawFd {
+		self.js.as_raw_fd()
+	}
+}
+
+impl Rumble for StatefulNativeJoystick {
+	fn supports_rumble(&self) -> bool {
+		self.js.supports_rumble()
+	}
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+		self.js.rumble(strong, weak, duration)
+	}
+}
+impl ::StatefulJoystick for StatefulNativeJoystick {
+	fn get_axis(&self, index: Axis) -> Option<i16> {
+		self.js.axis_number(index).and_then(|n| self.axes.get(n).cloned())
+	}
+	fn get_button(&self, index: Button) -> Option<bool> {
+		self.js.button_number(index).and_then(|n| self.buttons.get(n).cloned())
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.axes.get(index as usize).cloned()
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.buttons.get(index as usize).cloned()
+	}
+	fn get_axis_time(&self, index: Axis) -> Option<Duration> {
+		self.js.axis_number(index).and_then(|n| self.axis_times.get(n).and_then(|&time| time))
+	}
+	fn get_button_time(&self, index: Button) -> Option<Duration> {
+		self.js.button_number(index).and_then(|n| self.button_times.get(n).and_then(|&time| time))
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.js.hat_direction(index, &self.axes)
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
+
+/// A `js_event` record as read from the joystick interface
+#[repr(C)]
+struct JsEvent {
+	time: u32,
+	value: i16,
+	_type: u8,
+	number: u8
+}
+
+/// The types of event that are read from the joystick interface
+#[derive(Copy, Clone, PartialEq)]
+enum JsKind {
+	/// `JS_EVENT_BUTTON`
+	Button,
+	/// `JS_EVENT_AXIS`
+	Axis
+}
+
+/// An event read from the joystick interface
+#[derive(Copy, Clone)]
+pub struct LinuxEvent {
+	/// timestamp in milleseconds
+	time: u32,
+	/// value
+	value: i16,
+	/// event type, which is only a button or an axis
+	kind: JsKind,
+	/// whether this is part of the state the joystick had when it was opened
+	initial: bool,
+	/// axis / button number
+	number: u8,
+	/// the `ABS_*` / `BTN_*` code the axis / button is mapped to
+	code: u16,
+	/// the hat the axis is part of and the direction it is now pushed in
+	hat: Option<(u8, HatDirection)>
+}
+
+/// Convert the event
+///
+/// Axes and buttons are converted using the code the kernel maps them to, and
+/// hat switches are converted into `Event::HatMoved`, even for the initial state.
+/// Those with codes that don't have an `Axis` or `Button` are converted into raw
+/// events, including when they are part of the initial state. Events with unknown types
+/// never make it this far because `try_poll_native` returns them as an
+/// `Error::UnknownEvent`.
+pub fn convert_event(event: LinuxEvent) -> ::Event {
+	let initial = event.initial;
+	match event.kind {
+		JsKind::Button => match evdev::button_from_code(event.code) {
+			Some(button) if initial => ::Event::InitialButton(button, event.value != 0),
+			Some(button) if event.value == 0 => ::Event::ButtonReleased(button),
+			Some(button) => ::Event::ButtonPressed(button),
+			None => ::Event::RawButton(event.number, event.value != 0)
+		},
+		JsKind::Axis => match (event.hat, evdev::axis_from_code(event.code)) {
+			(Some((hat, direction)), _) => ::Event::HatMoved(hat, direction),
+			(None, Some(axis)) if initial => ::Event::InitialAxis(axis, event.value),
+			(None, Some(axis)) => ::Event::AxisMoved(axis, event.value),
+			(None, None) => ::Event::RawAxisMoved(event.number, event.value)
+		}
+	}
+}
+
+/// The correction the kernel applies to every axis of a joystick, in the order of their numbers
+///
+/// This is what the `jscal` program reads and writes.
+///
+/// ``` rust
+/// use reminisce::Joystick;
+/// use reminisce::linux::{Correction, NativeJoystick};
+/// if let Ok(mut js) = NativeJoystick::open(0) {
+///     if let Ok(mut calibration) = js.get_calibration() {
+///         // The first axis of this stick only reaches 20 to 230, centred at 128
+///         calibration.axes_mut()[0] = Correction::from_range(20, 128, 230, 4);
+///         js.set_calibration(&calibration).unwrap();
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Calibration {
+	axes: Vec<Correction>
+}
+
+impl Calibration {
+	/// Get the correction for each axis
+	pub fn axes(&self) -> &[Correction] {
+		&self.axes
+	}
+
+	/// Get the correction for each axis mutably
+	pub fn axes_mut(&mut self) -> &mut [Correction] {
+		&mut self.axes
+	}
+}
+
+/// The correction applied to an axis, laid out like the kernel's `js_corr`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Correction {
+	/// The coefficients, of which only the first four are used
+	///
+	/// These are the lower and upper bound of the centre, then the scale below
+	/// and above the centre as fixed point with 14 fractional bits.
+	pub coef: [i32; 8],
+	/// The precision of the axis, which isn't used by the kernel
+	pub prec: i16,
+	/// Whether the correction is used
+	_type: u16
+}
+
+impl Correction {
+	/// A correction that passes values through unchanged
+	pub fn none() -> Correction {
+		Correction {
+			coef: [0; 8],
+			prec: 0,
+			_type: JS_CORR_NONE
+		}
+	}
+
+	/// Compute the correction for an axis that was observed to reach `min` and
+	/// `max` and rests at `centre`
+	///
+	/// Values within `flat` of the centre are reported as 0. This uses the same
+	/// maths as the kernel's default correction.
+	///
+	/// ``` rust
+	/// use reminisce::linux::Correction;
+	/// let correction = Correction::from_range(20, 128, 230, 4);
+	/// assert_eq!(correction.apply(20), -32767);
+	/// assert_eq!(correction.apply(130), 0);
+	/// assert_eq!(correction.apply(230), 32767);
+	/// ```
+	pub fn from_range(min: i32, centre: i32, max: i32, flat: i32) -> Correction {
+		let (low, high) = (centre - flat, centre + flat);
+		let scale = |range: i32| if range > 0 { (1 << 29) / range } else { 0 };
+		Correction {
+			coef: [low, high, scale(low - min), scale(max - high), 0, 0, 0, 0],
+			prec: 0,
+			_type: JS_CORR_BROKEN
+		}
+	}
+
+	/// Check whether the correction changes the values
+	pub fn is_enabled(&self) -> bool {
+		self._type != JS_CORR_NONE
+	}
+
+	/// Apply the correction to a value the device reported, like the kernel does
+	pub fn apply(&self, value: i32) -> i32 {
+		if self._type != JS_CORR_BROKEN {
+			return value
+		}
+		let (value, coef) = (value as i64, &self.coef);
+		let corrected = if value <= coef[0] as i64 {
+			(coef[2] as i64 * (value - coef[0] as i64)) >> 14
+		} else if value < coef[1] as i64 {
+			0
+		} else {
+			(coef[3] as i64 * (value - coef[1] as i64)) >> 14
+		};
+		cmp::max(-32767, cmp::min(32767, corrected)) as i32
+	}
+}
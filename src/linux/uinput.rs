@@ -0,0 +1,405 @@
+use libc::{c_char, c_int, c_ulong, size_t, O_NONBLOCK, O_WRONLY, write};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::path::PathBuf;
+use evdev::{self, AbsInfo, ABS_HAT0X};
+use {Axis, Button, Error, Event, BUS_USB, BUS_VIRTUAL};
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0x00;
+const BTN_MODE: u16 = 0x13c;
+const NAME_LEN: usize = 80;
+
+extern {
+	fn open(path: *const c_char, oflag: c_int) -> c_int;
+	fn close(fd: c_int) -> c_int;
+	fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+/// Build an `_IOC(dir, 'U', nr, len)` request number
+fn uiioc(dir: c_ulong, nr: c_ulong, len: usize) -> c_ulong {
+	(dir << 30) | ((len as c_ulong) << 16) | (0x55 << 8) | nr
+}
+
+fn ui_dev_create() -> c_ulong {
+	uiioc(0, 1, 0)
+}
+
+fn ui_dev_destroy() -> c_ulong {
+	uiioc(0, 2, 0)
+}
+
+fn ui_dev_setup() -> c_ulong {
+	uiioc(1, 3, mem::size_of::<UinputSetup>())
+}
+
+fn ui_abs_setup() -> c_ulong {
+	uiioc(1, 4, mem::size_of::<UinputAbsSetup>())
+}
+
+fn ui_set_evbit() -> c_ulong {
+	uiioc(1, 100, mem::size_of::<c_int>())
+}
+
+fn ui_set_keybit() -> c_ulong {
+	uiioc(1, 101, mem::size_of::<c_int>())
+}
+
+fn ui_set_absbit() -> c_ulong {
+	uiioc(1, 103, mem::size_of::<c_int>())
+}
+
+fn ui_get_sysname(len: usize) -> c_ulong {
+	uiioc(2, 44, len)
+}
+
+/// A `uinput_setup` record
+#[repr(C)]
+struct UinputSetup {
+	bustype: u16,
+	vendor: u16,
+	product: u16,
+	version: u16,
+	name: [c_char; NAME_LEN],
+	ff_effects_max: u32
+}
+
+/// A `uinput_abs_setup` record
+#[repr(C)]
+struct UinputAbsSetup {
+	code: u16,
+	absinfo: AbsInfo
+}
+
+/// An `input_event` record to write to the device
+#[repr(C)]
+struct InputEvent {
+	time: [usize; 2],
+	_type: u16,
+	code: u16,
+	value: i32
+}
+
+/// Make the range of an axis
+fn abs_info(minimum: i32, maximum: i32, fuzz: i32, flat: i32) -> AbsInfo {
+	AbsInfo {
+		value: if minimum < 0 && maximum > 0 { 0 } else { minimum },
+		minimum: minimum,
+		maximum: maximum,
+		fuzz: fuzz,
+		flat: flat,
+		resolution: 0
+	}
+}
+
+/// Declares the name, ids, buttons and axes of a virtual gamepad before it is created
+///
+/// ``` rust
+/// use reminisce::{Axis, Button};
+/// use reminisce::linux::uinput::VirtualGamepadBuilder;
+/// let builder = VirtualGamepadBuilder::new("Remapped Pad")
+///     .with_buttons(&[Button::A, Button::B, Button::Start])
+///     .with_axis(Axis::LeftX, -32768, 32767)
+///     .with_axis(Axis::LeftY, -32768, 32767)
+///     .with_hat(0);
+/// // This needs write access to /dev/uinput
+/// if let Ok(mut pad) = builder.create() {
+///     pad.emit(reminisce::Event::ButtonPressed(Button::A)).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VirtualGamepadBuilder {
+	name: String,
+	bus: u16,
+	vendor: u16,
+	product: u16,
+	version: u16,
+	buttons: Vec<u16>,
+	axes: Vec<(u16, AbsInfo)>
+}
+
+impl VirtualGamepadBuilder {
+	/// Start declaring a virtual gamepad with no buttons or axes
+	pub fn new(name: &str) -> VirtualGamepadBuilder {
+		VirtualGamepadBuilder {
+			name: name.to_string(),
+			bus: BUS_VIRTUAL,
+			vendor: 0,
+			product: 0,
+			version: 1,
+			buttons: Vec::new(),
+			axes: Vec::new()
+		}
+	}
+
+	/// Start declaring a virtual gamepad laid out like an Xbox 360 controller, with
+	/// the ids of one so games recognise it
+	///
+	/// The triggers are axes from 0 to 255 and the directional pad is the first hat.
+	pub fn xbox(name: &str) -> VirtualGamepadBuilder {
+		VirtualGamepadBuilder::new(name)
+			.with_ids(BUS_USB, 0x045e, 0x028e, 0x0114)
+			.with_buttons(&[Button::A, Button::B, Button::X, Button::Y, Button::LeftShoulder,
+				Button::RightShoulder, Button::Select, Button::Start, Button::LeftStick, Button::RightStick])
+			.with_button_code(BTN_MODE)
+			.with_axis_code(evdev::axis_code(Axis::LeftX), -32768, 32767, 16, 128)
+			.with_axis_code(evdev::axis_code(Axis::LeftY), -32768, 32767, 16, 128)
+			.with_axis_code(evdev::axis_code(Axis::RightX), -32768, 32767, 16, 128)
+			.with_axis_code(evdev::axis_code(Axis::RightY), -32768, 32767, 16, 128)
+			.with_axis(Axis::TriggerLeft, 0, 255)
+			.with_axis(Axis::TriggerRight, 0, 255)
+			.with_hat(0)
+	}
+
+	/// Set the bus type, vendor id, product id and version of the device
+	pub fn with_ids(mut self, bus: u16, vendor: u16, product: u16, version: u16) -> VirtualGamepadBuilder {
+		self.bus = bus;
+		self.vendor = vendor;
+		self.product = product;
+		self.version = version;
+		self
+	}
+
+	/// Add a button
+	pub fn with_button(self, button: Button) -> VirtualGamepadBuilder {
+		self.with_button_code(evdev::button_code(button))
+	}
+
+	/// Add some buttons
+	pub fn with_buttons(self, buttons: &[Button]) -> VirtualGamepadBuilder {
+		buttons.iter().fold(self, |builder, &button| builder.with_button(button))
+	}
+
+	/// Add a button by its key code
+	pub fn with_button_code(mut self, code: u16) -> VirtualGamepadBuilder {
+		if !self.buttons.contains(&code) {
+			self.buttons.push(code);
+		}
+		self
+	}
+
+	/// Add an axis with a range
+	pub fn with_axis(self, axis: Axis, minimum: i32, maximum: i32) -> VirtualGamepadBuilder {
+		self.with_axis_code(evdev::axis_code(axis), minimum, maximum, 0, 0)
+	}
+
+	/// Add an axis by its absolute axis code, with a range, the noise the kernel
+	/// should filter out and the size of the area around the centre
+	pub fn with_axis_code(mut self, code: u16, minimum: i32, maximum: i32, fuzz: i32, flat: i32) -> VirtualGamepadBuilder {
+		let info = abs_info(minimum, maximum, fuzz, flat);
+		match self.axes.iter().position(|&(c, _)| c == code) {
+			Some(index) => self.axes[index].1 = info,
+			None => self.axes.push((code, info))
+		}
+		self
+	}
+
+	/// Add a hat switch, which is a pair of axes from -1 to 1
+	pub fn with_hat(self, hat: u8) -> VirtualGamepadBuilder {
+		let code = ABS_HAT0X + hat as u16 * 2;
+		self.with_axis_code(code, -1, 1, 0, 0).with_axis_code(code + 1, -1, 1, 0, 0)
+	}
+
+	/// Create the device through `/dev/uinput`
+	pub fn create(self) -> Result<VirtualGamepad, Error> {
+		let path = CString::new("/dev/uinput").unwrap();
+		let fd = unsafe { open(path.as_ptr(), O_WRONLY | O_NONBLOCK) };
+		if fd == -1 {
+			return Err(Error::last_os_error())
+		}
+		// The device is destroyed and closed if any of these fail
+		let pad = VirtualGamepad {
+			fd: fd,
+			buttons: self.buttons,
+			axes: self.axes
+		};
+		let mut setup = UinputSetup {
+			bustype: self.bus,
+			vendor: self.vendor,
+			product: self.product,
+			version: self.version,
+			name: [0; NAME_LEN],
+			ff_effects_max: 0
+		};
+		for (c, &byte) in setup.name.iter_mut().zip(self.name.as_bytes().iter().take(NAME_LEN - 1)) {
+			*c = byte as c_char;
+		}
+		unsafe {
+			if !pad.buttons.is_empty() {
+				try!(check(ioctl(fd, ui_set_evbit(), EV_KEY as c_int)));
+			}
+			for &code in pad.buttons.iter() {
+				try!(check(ioctl(fd, ui_set_keybit(), code as c_int)));
+			}
+			if !pad.axes.is_empty() {
+				try!(check(ioctl(fd, ui_set_evbit(), EV_ABS as c_int)));
+			}
+			for &(code, info) in pad.axes.iter() {
+				try!(check(ioctl(fd, ui_set_absbit(), code as c_int)));
+				let mut abs = UinputAbsSetup {
+					code: code,
+					absinfo: info
+				};
+				try!(check(ioctl(fd, ui_abs_setup(), &mut abs as *mut UinputAbsSetup)));
+			}
+			try!(check(ioctl(fd, ui_dev_setup(), &mut setup as *mut UinputSetup)));
+			try!(check(ioctl(fd, ui_dev_create())));
+		}
+		Ok(pad)
+	}
+}
+
+/// Turn the result of an `ioctl` into an error if it failed
+fn check(result: c_int) -> Result<(), Error> {
+	if result < 0 {
+		Err(Error::last_os_error())
+	} else {
+		Ok(())
+	}
+}
+
+/// A gamepad created through `/dev/uinput`, which the kernel and every other
+/// program sees as a real device
+///
+/// This can be read like any other joystick once it appears in `/dev/input/`,
+/// so it can be used to re-expose a joystick with a different layout:
+///
+/// ``` rust
+/// use reminisce::{scan, Button, Event, Joystick};
+/// use reminisce::linux::uinput::VirtualGamepadBuilder;
+/// if let (Some(mut js), Ok(mut pad)) = (scan().pop(), VirtualGamepadBuilder::xbox("Remapped Pad").create()) {
+///     for event in js.iter() {
+///         // Swap the A and B buttons
+///         pad.emit(match event {
+///             Event::ButtonPressed(Button::A) => Event::ButtonPressed(Button::B),
+///             Event::ButtonPressed(Button::B) => Event::ButtonPressed(Button::A),
+///             Event::ButtonReleased(Button::A) => Event::ButtonReleased(Button::B),
+///             Event::ButtonReleased(Button::B) => Event::ButtonReleased(Button::A),
+///             event => event
+///         }).unwrap();
+///     }
+/// }
+/// ```
+///
+/// The device is destroyed when this is dropped.
+pub struct VirtualGamepad {
+	fd: c_int,
+	buttons: Vec<u16>,
+	axes: Vec<(u16, AbsInfo)>
+}
+
+impl VirtualGamepad {
+	/// Emit an event and report it straight away
+	///
+	/// Axis values are scaled from between `MIN_AXIS_VALUE` and `MAX_AXIS_VALUE`
+	/// to the range of the axis, and raw buttons and axes are the ones declared
+	/// in that position. Events for buttons and axes the device doesn't have are
+	/// ignored.
+	pub fn emit(&mut self, event: Event) -> Result<(), Error> {
+		match event {
+			Event::ButtonPressed(button) =>
+				try!(self.emit_key(evdev::button_code(button), true)),
+			Event::ButtonReleased(button) =>
+				try!(self.emit_key(evdev::button_code(button), false)),
+			Event::InitialButton(button, pressed) =>
+				try!(self.emit_key(evdev::button_code(button), pressed)),
+			Event::RawButton(index, pressed) => match self.buttons.get(index as usize).cloned() {
+				Some(code) => try!(self.emit_key(code, pressed)),
+				None => return Ok(())
+			},
+			Event::AxisMoved(axis, value) | Event::InitialAxis(axis, value) =>
+				try!(self.emit_axis(evdev::axis_code(axis), value)),
+			Event::RawAxisMoved(index, value) => match self.axes.get(index as usize).map(|&(code, _)| code) {
+				Some(code) => try!(self.emit_axis(code, value)),
+				None => return Ok(())
+			},
+			Event::HatMoved(hat, direction) => {
+				let (x, y) = direction.to_axes();
+				let code = ABS_HAT0X + hat as u16 * 2;
+				try!(self.emit_raw(EV_ABS, code, x as i32));
+				try!(self.emit_raw(EV_ABS, code + 1, y as i32));
+			}
+		}
+		self.sync()
+	}
+
+	/// Emit a key event if the device has the key
+	fn emit_key(&mut self, code: u16, pressed: bool) -> Result<(), Error> {
+		if self.buttons.contains(&code) {
+			self.emit_raw(EV_KEY, code, if pressed { 1 } else { 0 })
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Emit an absolute axis event scaled to its range if the device has the axis
+	fn emit_axis(&mut self, code: u16, value: i16) -> Result<(), Error> {
+		match self.axes.iter().find(|&&(c, _)| c == code).map(|&(_, info)| info) {
+			Some(info) => self.emit_raw(EV_ABS, code, info.denormalise(value)),
+			None => Ok(())
+		}
+	}
+
+	/// Emit an event with a type, code and value without reporting it
+	///
+	/// The kernel only passes events on once `sync` is called.
+	pub fn emit_raw(&mut self, _type: u16, code: u16, value: i32) -> Result<(), Error> {
+		let event = InputEvent {
+			time: [0, 0],
+			_type: _type,
+			code: code,
+			value: value
+		};
+		let size = mem::size_of::<InputEvent>() as size_t;
+		if unsafe { write(self.fd, &event as *const InputEvent as *const _, size) } == -1 {
+			Err(Error::last_os_error())
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Report the events emitted since the last report
+	pub fn sync(&mut self) -> Result<(), Error> {
+		self.emit_raw(EV_SYN, SYN_REPORT, 0)
+	}
+
+	/// Get the path to the device in sysfs, like `/sys/devices/virtual/input/input12`
+	pub fn get_sys_path(&self) -> Result<PathBuf, Error> {
+		let mut name = [0 as c_char; 64];
+		try!(check(unsafe { ioctl(self.fd, ui_get_sysname(name.len()), name.as_mut_ptr()) }));
+		name[name.len() - 1] = 0;
+		let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+		Ok(PathBuf::from("/sys/devices/virtual/input").join(&*name.to_string_lossy()))
+	}
+
+	/// Find the index of a device node of this gamepad from its name, like `js0`
+	fn find_index<F>(&self, index: F) -> Option<u8> where F:Fn(&str) -> Option<u8> {
+		let entries = match self.get_sys_path().and_then(|path| ::std::fs::read_dir(path).map_err(Error::from)) {
+			Ok(entries) => entries,
+			Err(_) => return None
+		};
+		entries.filter_map(|entry| entry.ok()).filter_map(|entry| entry.file_name().to_str().and_then(|name| index(name))).next()
+	}
+
+	/// Get the index to open this gamepad with through the joystick interface, once udev has made it
+	pub fn get_joystick_index(&self) -> Option<u8> {
+		self.find_index(::linux::device_index)
+	}
+
+	/// Get the index to open this gamepad with through evdev, once udev has made it
+	pub fn get_event_index(&self) -> Option<u8> {
+		self.find_index(evdev::device_index)
+	}
+}
+
+impl Drop for VirtualGamepad {
+	/// Destroy the device and close `/dev/uinput`
+	fn drop(&mut self) {
+		unsafe {
+			ioctl(self.fd, ui_dev_destroy());
+			close(self.fd);
+		}
+	}
+}
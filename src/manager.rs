@@ -0,0 +1,332 @@
+use libc::{c_char, c_int, size_t, read};
+use std::collections::{BTreeMap, VecDeque};
+use std::collections::btree_map::Values;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+use std::os::unix::io::{AsRawFd, RawFd};
+use {Error, Event, Joystick, NativeJoystick, StatefulJoystick};
+
+const IN_NONBLOCK: c_int = 0o4000;
+const IN_CLOEXEC: c_int = 0o2000000;
+const IN_ATTRIB: u32 = 0x004;
+const IN_CREATE: u32 = 0x100;
+const IN_DELETE: u32 = 0x200;
+
+extern {
+	fn inotify_init1(flags: c_int) -> c_int;
+	fn inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int;
+	fn close(fd: c_int) -> c_int;
+}
+
+/// The header of an event read from inotify, which is followed by `len` bytes of name
+#[repr(C)]
+struct InotifyEvent {
+	wd: c_int,
+	mask: u32,
+	cookie: u32,
+	len: u32
+}
+
+/// A joystick that is opened from a device file in `/dev/input/`
+///
+/// The joystick interface and evdev backends use different device files, so the
+/// manager asks the joystick type which ones belong to it.
+pub trait DeviceJoystick : Joystick {
+	/// Get the index to open the joystick with from the name of its device file,
+	/// or none if the file isn't for this kind of joystick
+	fn device_index(name: &str) -> Option<u8>;
+}
+
+impl DeviceJoystick for ::linux::NativeJoystick {
+	/// This accepts `js...` files
+	fn device_index(name: &str) -> Option<u8> {
+		::linux::device_index(name)
+	}
+}
+
+impl DeviceJoystick for ::linux::StatefulNativeJoystick {
+	/// This accepts `js...` files
+	fn device_index(name: &str) -> Option<u8> {
+		::linux::device_index(name)
+	}
+}
+
+impl DeviceJoystick for ::evdev::NativeJoystick {
+	/// This accepts `event...` files
+	fn device_index(name: &str) -> Option<u8> {
+		::evdev::device_index(name)
+	}
+}
+
+impl DeviceJoystick for ::evdev::StatefulNativeJoystick {
+	/// This accepts `event...` files
+	fn device_index(name: &str) -> Option<u8> {
+		::evdev::device_index(name)
+	}
+}
+
+impl<J> DeviceJoystick for ::DPadFromHat<J> where J:DeviceJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+#[cfg(feature = "mappings")]
+impl<J> DeviceJoystick for ::JoystickMapper<J> where J:DeviceJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+impl<J> DeviceJoystick for ::DeadZoneJoystick<J> where J:DeviceJoystick+StatefulJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+impl<J> DeviceJoystick for ::CurveJoystick<J> where J:DeviceJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+impl<J> DeviceJoystick for ::FilterJoystick<J> where J:DeviceJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+impl<J> DeviceJoystick for ::SnapshotJoystick<J> where J:DeviceJoystick+StatefulJoystick {
+	fn device_index(name: &str) -> Option<u8> {
+		J::device_index(name)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// An event emitted by a `JoystickManager`
+pub enum ManagerEvent {
+	/// Fired when a joystick is plugged in and opened, with its index
+	///
+	/// This is also fired for every joystick that was plugged in when the manager was created.
+	Connected(u8),
+	/// Fired when a joystick is unplugged, with its index
+	///
+	/// This is also fired for a joystick that fails to be read with any error other
+	/// than `Error::UnknownEvent`, which isn't polled again. The joystick is closed
+	/// before this event is returned, unless it is handed back by
+	/// `JoystickManager::poll_detached`.
+	Disconnected(u8),
+	/// Fired when a joystick emits an event, with its index
+	Input(u8, Event)
+}
+
+/// Owns every joystick plugged in and keeps track of them as they are plugged in and out
+///
+/// This watches `/dev/input/` with inotify, so joysticks are only opened once when they
+/// appear instead of re-scanning for them. Only the device files the joystick type
+/// accepts are opened, so a manager of evdev joysticks opens `event...` files and
+/// a manager of joystick interface joysticks opens `js...` files. Joysticks are
+/// polled in turn, so one busy joystick can't hold back the others.
+///
+/// ``` rust
+/// use reminisce::{JoystickManager, ManagerEvent, NativeJoystick};
+/// if let Ok(mut manager) = JoystickManager::<NativeJoystick>::new() {
+///     for event in manager.iter() {
+///         match event {
+///             ManagerEvent::Connected(index) => println!("Joystick #{} plugged in", index),
+///             ManagerEvent::Disconnected(index) => println!("Joystick #{} unplugged", index),
+///             ManagerEvent::Input(index, event) => println!("Joystick #{}: {:?}", index, event)
+///         }
+///     }
+/// }
+/// ```
+pub struct JoystickManager<J = NativeJoystick> where J:DeviceJoystick {
+	inotify: c_int,
+	joysticks: BTreeMap<u8, J>,
+	/// Hotplug events, with the joysticks that were unplugged
+	pending: VecDeque<(ManagerEvent, Option<J>)>,
+	/// The index of the joystick to poll first next time
+	next: u8
+}
+
+impl<J> JoystickManager<J> where J:DeviceJoystick {
+	/// Start watching `/dev/input/` and open every joystick that is already plugged in
+	pub fn new() -> Result<JoystickManager<J>, Error> {
+		let inotify = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+		if inotify == -1 {
+			return Err(Error::last_os_error())
+		}
+		let mut manager = JoystickManager {
+			inotify: inotify,
+			joysticks: BTreeMap::new(),
+			pending: VecDeque::with_capacity(4),
+			next: 0
+		};
+		let path = CString::new("/dev/input/").unwrap();
+		if unsafe { inotify_add_watch(inotify, path.as_ptr(), IN_CREATE | IN_ATTRIB | IN_DELETE) } == -1 {
+			return Err(Error::last_os_error())
+		}
+		let entries = try!(::std::fs::read_dir("/dev/input/"));
+		for entry in entries {
+			if let Some(name) = try!(entry).file_name().to_str() {
+				manager.added(name);
+			}
+		}
+		Ok(manager)
+	}
+
+	/// Get the joystick with the given index, if it is plugged in
+	pub fn get(&self, index: u8) -> Option<&J> {
+		self.joysticks.get(&index)
+	}
+
+	/// Get the joystick with the given index mutably, if it is plugged in
+	pub fn get_mut(&mut self, index: u8) -> Option<&mut J> {
+		self.joysticks.get_mut(&index)
+	}
+
+	/// Iterate through the joysticks that are plugged in
+	pub fn joysticks(&self) -> Values<u8, J> {
+		self.joysticks.values()
+	}
+
+	/// Get the number of joysticks that are plugged in
+	pub fn len(&self) -> usize {
+		self.joysticks.len()
+	}
+
+	/// Poll for hotplug events and events from every joystick in non-blocking mode
+	///
+	/// Hotplug events are returned before any input events.
+	pub fn poll(&mut self) -> Option<ManagerEvent> {
+		self.poll_detached().map(|(event, _)| event)
+	}
+
+	/// Poll like `poll`, but hand back the joystick with `ManagerEvent::Disconnected`
+	/// instead of closing it
+	///
+	/// This lets anything watching the joystick's file descriptor stop watching it
+	/// before the descriptor is closed by dropping the joystick.
+	pub fn poll_detached(&mut self) -> Option<(ManagerEvent, Option<J>)> {
+		if self.pending.is_empty() {
+			self.read_inotify();
+		}
+		if let Some(pending) = self.pending.pop_front() {
+			return Some(pending)
+		}
+		// Start after the joystick that last had an event
+		let order: Vec<u8> = self.joysticks.range(self.next..)
+			.chain(self.joysticks.range(..self.next))
+			.map(|(&index, _)| index)
+			.collect();
+		let mut disconnected = None;
+		for index in order {
+			let joystick = self.joysticks.get_mut(&index).unwrap();
+			match joystick.try_poll() {
+				Ok(Some(event)) => {
+					self.next = index.wrapping_add(1);
+					return Some((ManagerEvent::Input(index, event), None))
+				},
+				Err(Error::UnknownEvent(..)) => (),
+				// A joystick that can't be read any more is treated as unplugged
+				Err(_) => {
+					disconnected = Some(index);
+					break
+				},
+				_ if !joystick.is_connected() => {
+					disconnected = Some(index);
+					break
+				},
+				_ => ()
+			}
+		}
+		disconnected.and_then(|index| self.removed(index))
+	}
+
+	/// Iterate through the events that haven't been processed yet
+	pub fn iter(&mut self) -> ManagerPoller<J> {
+		ManagerPoller {
+			manager: self
+		}
+	}
+
+	/// Try to open the joystick with the device file name `name` if it isn't open already
+	fn added(&mut self, name: &str) {
+		if let Some(index) = J::device_index(name) {
+			if !self.joysticks.contains_key(&index) {
+				if let Ok(js) = J::open(index) {
+					self.joysticks.insert(index, js);
+					self.pending.push_back((ManagerEvent::Connected(index), None));
+				}
+			}
+		}
+	}
+
+	/// Stop managing the joystick with the given index and hand it back
+	fn removed(&mut self, index: u8) -> Option<(ManagerEvent, Option<J>)> {
+		self.joysticks.remove(&index).map(|js| (ManagerEvent::Disconnected(index), Some(js)))
+	}
+
+	/// Read every change to `/dev/input/` since the last poll
+	fn read_inotify(&mut self) {
+		let mut buffer = [0u8; 4096];
+		loop {
+			let size = unsafe { read(self.inotify, buffer.as_mut_ptr() as *mut _, buffer.len() as size_t) };
+			if size <= 0 {
+				return
+			}
+			let mut offset = 0;
+			while offset + mem::size_of::<InotifyEvent>() <= size as usize {
+				// The buffer is only aligned for bytes, so the header is copied out of it
+				let event = unsafe { ptr::read_unaligned(buffer[offset..].as_ptr() as *const InotifyEvent) };
+				let (mask, len) = (event.mask, event.len as usize);
+				let start = offset + mem::size_of::<InotifyEvent>();
+				offset = start + len;
+				if len == 0 {
+					continue
+				}
+				let name = unsafe { CStr::from_ptr(buffer[start..].as_ptr() as *const c_char) };
+				if let Ok(name) = name.to_str() {
+					if mask & IN_DELETE != 0 {
+						if let Some(removed) = J::device_index(name).and_then(|index| self.removed(index)) {
+							self.pending.push_back(removed)
+						}
+					} else {
+						// udev may only make the device readable after creating it, so try again on IN_ATTRIB
+						self.added(name)
+					}
+				}
+			}
+		}
+	}
+}
+
+impl<J> AsRawFd for JoystickManager<J> where J:DeviceJoystick {
+	/// This is the inotify descriptor, which is readable when a joystick is plugged in or out
+	fn as_raw_fd(&self) -> RawFd {
+		self.inotify
+	}
+}
+
+impl<J> Drop for JoystickManager<J> where J:DeviceJoystick {
+	/// Stop watching `/dev/input/`
+	fn drop(&mut self) {
+		unsafe {
+			close(self.inotify);
+		}
+	}
+}
+
+/// An iterator over a joystick manager's events
+pub struct ManagerPoller<'a, J> where J:DeviceJoystick+'a {
+	manager: &'a mut JoystickManager<J>
+}
+
+impl<'a, J> Iterator for ManagerPoller<'a, J> where J:DeviceJoystick {
+	type Item = ManagerEvent;
+	/// This calls the `manager.poll()` method to poll for the next event
+	fn next(&mut self) -> Option<ManagerEvent> {
+		self.manager.poll()
+	}
+}
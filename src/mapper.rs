@@ -1,15 +1,215 @@
-use {Axis, Button, Event, Joystick, StatefulJoystick};
-use std::borrow::Cow;
-use std::collections::BTreeMap;
+use {Axis, Button, Error, Event, Guid, HatDirection, Joystick, StatefulJoystick, TimedEvent, MAX_AXIS_VALUE, NUM_AXES, NUM_BUTTONS};
+use std::cmp;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
 use std::rc::Rc;
+use std::slice::Iter;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How far an axis has to be pushed to press a button, by default
+pub static DEFAULT_THRESHOLD: i16 = 16384;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The part of an axis that a binding uses
+pub enum AxisRange {
+    /// The whole axis
+    Full,
+    /// The positive half of the axis, from the centre to `MAX_AXIS_VALUE`
+    Positive,
+    /// The negative half of the axis, from the centre to `MIN_AXIS_VALUE`
+    Negative
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The button or axis that a binding reads from
+pub enum Source {
+    /// A button
+    Button(Button),
+    /// A button without a `Button`, with its index
+    RawButton(u8),
+    /// A part of an axis
+    Axis(Axis, AxisRange),
+    /// A part of an axis without an `Axis`, with its index
+    RawAxis(u8, AxisRange),
+    /// A direction of a hat, with the hat's index, which acts like a button
+    ///
+    /// The direction should be `Up`, `Down`, `Left` or `Right`.
+    Hat(u8, HatDirection)
+}
+
+impl Source {
+    /// Check whether this reads from the same button or axis as `other`
+    fn same_input(&self, other: &Source) -> bool {
+        match (*self, *other) {
+            (Source::Button(a), Source::Button(b)) => a == b,
+            (Source::RawButton(a), Source::RawButton(b)) => a == b,
+            (Source::Axis(a, _), Source::Axis(b, _)) => a == b,
+            (Source::RawAxis(a, _), Source::RawAxis(b, _)) => a == b,
+            (Source::Hat(a, x), Source::Hat(b, y)) => a == b && x == y,
+            _ => false
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The button or axis that a binding writes to
+pub enum Target {
+    /// A button
+    Button(Button),
+    /// A part of an axis
+    Axis(Axis, AxisRange)
+}
+
+impl Target {
+    /// Check whether this writes to the same button or axis as `other`
+    fn same_output(&self, other: &Target) -> bool {
+        match (*self, *other) {
+            (Target::Button(a), Target::Button(b)) => a == b,
+            (Target::Axis(a, _), Target::Axis(b, _)) => a == b,
+            _ => false
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A binding from a button or axis of the joystick to a button or axis of the mapper
+///
+/// A button reads as `MAX_AXIS_VALUE` when it is pressed and 0 when it is
+/// released. A button target is pressed when its value is over the threshold,
+/// so a whole axis only presses a button when it is pushed in the positive direction.
+/// A button or half axis that drives a whole axis is stretched over the whole axis.
+///
+/// ``` rust
+/// use reminisce::{Axis, Button};
+/// use reminisce::mapper::{AxisRange, Binding, Source, Target};
+/// // A directional pad reported as an axis
+/// let left = Binding::new(Source::Axis(Axis::LeftX, AxisRange::Negative), Target::Button(Button::DPadLeft));
+/// assert_eq!(left.apply(-32767), 32767);
+/// assert_eq!(left.apply(32767), 0);
+/// // A trigger reported as a button
+/// let trigger = Binding::new(Source::Button(Button::LeftTrigger), Target::Axis(Axis::TriggerLeft, AxisRange::Full));
+/// assert_eq!(trigger.apply(0), -32767);
+/// // An inverted Y axis
+/// let mut y = Binding::new(Source::Axis(Axis::LeftY, AxisRange::Full), Target::Axis(Axis::LeftY, AxisRange::Full));
+/// y.inverted = true;
+/// assert_eq!(y.apply(1000), -1000);
+/// ```
+pub struct Binding {
+    /// What this reads from
+    pub source: Source,
+    /// What this writes to
+    pub target: Target,
+    /// Whether the source is inverted before anything else is done to it
+    pub inverted: bool,
+    /// How far the source has to go past to press a button target
+    pub threshold: i16
+}
+
+impl Binding {
+    /// Make a binding that isn't inverted and uses the `DEFAULT_THRESHOLD`
+    pub fn new(source: Source, target: Target) -> Binding {
+        Binding {
+            source: source,
+            target: target,
+            inverted: false,
+            threshold: DEFAULT_THRESHOLD
+        }
+    }
+
+    /// Convert a value of the source into the value of the target
+    pub fn apply(&self, value: i16) -> i16 {
+        let max = MAX_AXIS_VALUE as i32;
+        let mut value = value as i32;
+        let half = match self.source {
+            Source::Button(_) | Source::RawButton(_) | Source::Hat(_, _) => {
+                if self.inverted {
+                    value = max - value
+                }
+                true
+            },
+            Source::Axis(_, range) | Source::RawAxis(_, range) => {
+                if self.inverted {
+                    value = -value
+                }
+                match range {
+                    AxisRange::Full => false,
+                    AxisRange::Positive => {
+                        value = cmp::max(value, 0);
+                        true
+                    },
+                    AxisRange::Negative => {
+                        value = cmp::max(-value, 0);
+                        true
+                    }
+                }
+            }
+        };
+        let value = match self.target {
+            Target::Button(_) => if value > self.threshold as i32 { max } else { 0 },
+            Target::Axis(_, range) => {
+                let value = match (range, half) {
+                    (AxisRange::Full, true) => value * 2 - max,
+                    (AxisRange::Full, false) => value,
+                    (_, true) => value,
+                    (_, false) => (value + max) / 2
+                };
+                if range == AxisRange::Negative { -value } else { value }
+            }
+        };
+        cmp::max(-max, cmp::min(max, value)) as i16
+    }
+}
+
+/// Combine the values of the bindings to a target into the value of the target
+///
+/// A button is pressed if any of its bindings press it. A binding to the whole
+/// of an axis takes priority over bindings to its halves, which are added up.
+fn combine<I>(target: Target, values: I) -> Option<i16> where I:Iterator<Item = (Target, Option<i16>)> {
+    let (mut value, mut full) = (None, None);
+    for (to, output) in values {
+        if let Some(output) = output {
+            value = Some(match (to, value) {
+                (Target::Button(_), Some(v)) => cmp::max(v, output),
+                (Target::Axis(_, AxisRange::Full), _) => {
+                    full = Some(output);
+                    output
+                },
+                (_, Some(v)) => v.saturating_add(output),
+                (_, None) => output
+            });
+        }
+    }
+    match target {
+        Target::Button(_) => value,
+        Target::Axis(_, _) => full.or(value)
+    }
+}
 
 /// A Joystick mapper
 ///
-/// This allows you to map a joystick's buttons and axes
+/// This allows you to map a joystick's buttons and axes to other buttons and axes,
+/// including between buttons and axes. Buttons and axes that aren't the source of
+/// any binding are passed through unchanged.
+///
+/// ``` rust
+/// use reminisce::{Button, Event, Joystick, JoystickMapper, VirtualJoystick};
+/// let js = VirtualJoystick::new(0, "Test Pad");
+/// let controller = js.controller();
+/// let mut mapper = JoystickMapper::new(js);
+/// mapper.map_button(Button::A, Button::B);
+/// controller.press(Button::A);
+/// controller.press(Button::X);
+/// assert_eq!(mapper.poll(), Some(Event::ButtonPressed(Button::B)));
+/// assert_eq!(mapper.poll(), Some(Event::ButtonPressed(Button::X)));
+/// assert_eq!(mapper.poll(), None);
+/// ```
 pub struct JoystickMapper<J> where J:Joystick {
     joystick: J,
-    axes: Rc<BTreeMap<Axis, Axis>>,
-    buttons: Rc<BTreeMap<Button, Button>>
+    bindings: Rc<Vec<Binding>>,
+    outputs: Vec<i16>,
+    pending: VecDeque<TimedEvent>
 }
 impl<J> JoystickMapper<J> where J:Joystick {
 
@@ -17,124 +217,609 @@ impl<J> JoystickMapper<J> where J:Joystick {
     pub fn new(joystick: J) -> JoystickMapper<J> {
         JoystickMapper {
             joystick: joystick,
-            axes: Rc::new(BTreeMap::new()),
-            buttons: Rc::new(BTreeMap::new())
+            bindings: Rc::new(Vec::new()),
+            outputs: Vec::new(),
+            pending: VecDeque::new()
         }
     }
 
+    /// Start mapping a joystick with an SDL mapping
+    pub fn from_mapping(joystick: J, mapping: &Mapping) -> JoystickMapper<J> {
+        let mut mapper = JoystickMapper::new(joystick);
+        mapper.apply_mapping(mapping);
+        mapper
+    }
+
+    /// Start mapping a joystick with the mapping in the database for its GUID
+    ///
+    /// If the database doesn't have a mapping for the joystick, nothing is mapped.
+    ///
+    /// ``` rust
+    /// use reminisce::{Joystick, JoystickMapper, MappingDatabase, NativeJoystick};
+    /// let database = MappingDatabase::parse("030000005e0400008e02000014010000,X360 Controller,a:b1,b:b0,platform:Linux,").unwrap();
+    /// if let Ok(js) = NativeJoystick::open(0) {
+    ///     let mapper = JoystickMapper::from_database(js, &database);
+    ///     println!("{}", mapper.to_mapping());
+    /// }
+    /// ```
+    pub fn from_database(joystick: J, database: &MappingDatabase) -> JoystickMapper<J> {
+        let guid = joystick.get_info().guid;
+        let mut mapper = JoystickMapper::new(joystick);
+        if let Some(mapping) = database.get(&guid) {
+            mapper.apply_mapping(mapping);
+        }
+        mapper
+    }
+
+    /// Bind the buttons and axes as an SDL mapping does
+    ///
+    /// Button sources like `b0` and axis sources like `a0` are the numbers SDL gives
+    /// the joystick's buttons and axes, which are looked up with
+    /// `Joystick::get_mapping_button` and `Joystick::get_mapping_axis`. Hat sources
+    /// like `h0.1` are the numbers of the hats the backend reports. Bindings to
+    /// anything without a `Button` or `Axis`, or from anything the joystick doesn't
+    /// have, are ignored.
+    pub fn apply_mapping(&mut self, mapping: &Mapping) {
+        for &(ref key, ref value) in mapping.fields.iter() {
+            if let Some(binding) = parse_binding(&self.joystick, key, value) {
+                self.bind(binding)
+            }
+        }
+    }
+
+    /// Describe how this maps the joystick as an SDL mapping
+    ///
+    /// This has a binding for every button and axis that is passed through
+    /// unchanged too. Thresholds can't be written in SDL mappings so they are lost,
+    /// and so are bindings from anything SDL doesn't give a number.
+    pub fn to_mapping(&self) -> Mapping {
+        let info = self.joystick.get_info();
+        let mut fields = Vec::with_capacity(24);
+        let buttons = (0..NUM_BUTTONS).filter_map(Button::from_index)
+            .map(|button| Binding::new(Source::Button(button), Target::Button(button)));
+        let axes = (0..NUM_AXES).filter_map(Axis::from_index)
+            .map(|axis| Binding::new(Source::Axis(axis, AxisRange::Full), Target::Axis(axis, AxisRange::Full)));
+        let identities = buttons.chain(axes).filter(|identity| self.bindings.iter().all(|b| !b.source.same_input(&identity.source)));
+        for binding in identities.chain(self.bindings.iter().cloned()) {
+            if let Some(field) = format_binding(&binding, self.source_number(binding.source)) {
+                fields.push(field)
+            }
+        }
+        fields.push(("platform".to_string(), PLATFORM.to_string()));
+        Mapping {
+            guid: info.guid,
+            name: info.name,
+            fields: fields
+        }
+    }
+
+    /// Add a binding, replacing the binding that reads from the same source
+    pub fn bind(&mut self, binding: Binding) {
+        let existing = self.bindings.iter().position(|b| b.source == binding.source);
+        match existing {
+            Some(index) => self.bindings.make_unique()[index] = binding,
+            None => self.bindings.make_unique().push(binding)
+        }
+        self.outputs.resize(self.bindings.len(), 0);
+    }
+
+    /// Get the bindings
+    pub fn get_bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
     /// Map a button to another button
     pub fn map_button(&mut self, from: Button, to: Button) {
-        self.buttons.make_unique().insert(from, to);
+        self.bind(Binding::new(Source::Button(from), Target::Button(to)))
     }
 
     /// Map a multitude of buttons to other buttons
-    ///
-    /// This is more efficient than doing them inidividually
     pub fn map_buttons<I>(&mut self, iter: I) where I:Iterator<Item = (Button, Button)> {
-        let mut buttons = self.buttons.make_unique();
         for (from, to) in iter {
-            buttons.insert(from, to);
+            self.map_button(from, to)
         }
     }
 
     /// Map an axis to another axis
     pub fn map_axis(&mut self, from: Axis, to: Axis) {
-        self.axes.make_unique().insert(from, to);
+        self.bind(Binding::new(Source::Axis(from, AxisRange::Full), Target::Axis(to, AxisRange::Full)))
     }
 
     /// Map a multitude of axes to other axes
-    ///
-    /// This is more efficient than doing them inidividually
     pub fn map_axes<I>(&mut self, iter: I) where I:Iterator<Item = (Axis, Axis)> {
-        let mut axes = self.axes.make_unique();
         for (from, to) in iter {
-            axes.insert(from, to);
+            self.map_axis(from, to)
         }
     }
 
+    /// Get the number SDL mappings give the button or axis a source reads from
+    fn source_number(&self, source: Source) -> Option<u8> {
+        (0..255).find(|&number| {
+            let found = match source {
+                Source::Button(_) | Source::RawButton(_) => self.joystick.get_mapping_button(number),
+                Source::Axis(_, _) | Source::RawAxis(_, _) => self.joystick.get_mapping_axis(number),
+                Source::Hat(_, _) => None
+            };
+            found.map_or(false, |found| found.same_input(&source))
+        })
+    }
+
+    /// Get the value of a target from the outputs of its bindings
+    fn output(&self, target: Target) -> Option<i16> {
+        combine(target, self.bindings.iter().zip(self.outputs.iter())
+            .filter(|&(b, _)| b.target.same_output(&target))
+            .map(|(b, &output)| (b.target, Some(output))))
+    }
+
+    /// Run an event from the joystick through the bindings that read from `source`
+    /// and queue the events for the targets that changed
+    fn map_event(&mut self, timed: TimedEvent, source: Source, value: i16, initial: bool) {
+        let mut targets: Vec<Target> = Vec::with_capacity(2);
+        for binding in self.bindings.iter().filter(|b| b.source.same_input(&source)) {
+            if !targets.iter().any(|t| t.same_output(&binding.target)) {
+                targets.push(binding.target)
+            }
+        }
+        let before: Vec<Option<i16>> = targets.iter().map(|&t| self.output(t)).collect();
+        for (binding, output) in self.bindings.iter().zip(self.outputs.iter_mut()) {
+            if binding.source.same_input(&source) {
+                *output = binding.apply(value)
+            }
+        }
+        for (&target, before) in targets.iter().zip(before.into_iter()) {
+            let after = self.output(target);
+            if !initial && before == after {
+                continue
+            }
+            let value = after.unwrap_or(0);
+            let event = match target {
+                Target::Button(button) if initial => Event::InitialButton(button, value != 0),
+                Target::Button(button) if value != 0 => Event::ButtonPressed(button),
+                Target::Button(button) => Event::ButtonReleased(button),
+                Target::Axis(axis, _) if initial => Event::InitialAxis(axis, value),
+                Target::Axis(axis, _) => Event::AxisMoved(axis, value)
+            };
+            self.pending.push_back(TimedEvent {
+                event: event,
+                .. timed
+            })
+        }
+    }
 }
 impl<J> Joystick for JoystickMapper<J> where J:Joystick {
     type WithState = JoystickMapper<<J as Joystick>::WithState>;
     type NativeEvent = <J as Joystick>::NativeEvent;
-    type OpenError = <J as Joystick>::OpenError;
 
-    fn open(index: u8) -> Result<JoystickMapper<J>, <J as Joystick>::OpenError> {
-        Ok(JoystickMapper {
-            joystick: try!(Joystick::open(index)),
-            axes: Rc::new(BTreeMap::new()),
-            buttons: Rc::new(BTreeMap::new())
-        })
+    fn open(index: u8) -> Result<JoystickMapper<J>, Error> {
+        Ok(JoystickMapper::new(try!(Joystick::open(index))))
     }
 
-    fn is_connected(&self) -> bool {
-        self.joystick.is_connected()
+    delegate_joystick!(methods);
+    /// This runs the events from the joystick through the bindings, so one
+    /// event can turn into several events or none at all
+    fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+        loop {
+            if let Some(timed) = self.pending.pop_front() {
+                return Ok(Some(timed))
+            }
+            let timed = match try!(self.joystick.try_poll_timed()) {
+                Some(timed) => timed,
+                None => return Ok(None)
+            };
+            let max = MAX_AXIS_VALUE;
+            let (source, value, initial) = match timed.event {
+                Event::ButtonPressed(btn) => (Source::Button(btn), max, false),
+                Event::ButtonReleased(btn) => (Source::Button(btn), 0, false),
+                Event::InitialButton(btn, pressed) => (Source::Button(btn), if pressed { max } else { 0 }, true),
+                Event::RawButton(index, pressed) => (Source::RawButton(index), if pressed { max } else { 0 }, false),
+                Event::AxisMoved(axis, value) => (Source::Axis(axis, AxisRange::Full), value, false),
+                Event::InitialAxis(axis, value) => (Source::Axis(axis, AxisRange::Full), value, true),
+                Event::RawAxisMoved(index, value) => (Source::RawAxis(index, AxisRange::Full), value, false),
+                Event::HatMoved(hat, direction) => {
+                    if self.bindings.iter().all(|b| match b.source { Source::Hat(h, _) => h != hat, _ => true }) {
+                        return Ok(Some(timed))
+                    }
+                    for &way in [HatDirection::Up, HatDirection::Down, HatDirection::Left, HatDirection::Right].iter() {
+                        let value = if direction.contains(way) { max } else { 0 };
+                        self.map_event(timed, Source::Hat(hat, way), value, false)
+                    }
+                    continue
+                }
+            };
+            if self.bindings.iter().all(|b| !b.source.same_input(&source)) {
+                return Ok(Some(timed))
+            }
+            self.map_event(timed, source, value, initial)
+        }
     }
-    fn get_id(&self) -> Cow<str> {
-        self.joystick.get_id()
+    fn with_state(self) -> JoystickMapper<<J as Joystick>::WithState> {
+        JoystickMapper {
+            joystick: self.joystick.with_state(),
+            bindings: self.bindings.clone(),
+            outputs: self.outputs,
+            pending: self.pending
+        }
     }
-    fn get_index(&self) -> u8 {
-        self.joystick.get_index()
+}
+impl<J> JoystickMapper<J> where J:StatefulJoystick {
+    /// Read the value of a source from the joystick's state
+    fn read(&self, source: Source) -> Option<i16> {
+        let pressed = |pressed| if pressed { MAX_AXIS_VALUE } else { 0 };
+        match source {
+            Source::Button(button) => self.joystick.get_button(button).map(pressed),
+            Source::RawButton(index) => self.joystick.get_raw_button(index).map(pressed),
+            Source::Axis(axis, _) => self.joystick.get_axis(axis),
+            Source::RawAxis(index, _) => self.joystick.get_raw_axis(index),
+            Source::Hat(hat, way) => self.joystick.get_hat(hat).map(|direction| pressed(direction.contains(way)))
+        }
     }
-    fn get_num_axes(&self) -> u8 {
-        self.joystick.get_num_axes()
+
+    /// Get the value of a target from the joystick's state, or `None` if it has no
+    /// bindings and its own button or axis is bound to something else
+    fn read_target(&self, target: Target, identity: Source) -> Option<i16> {
+        if self.bindings.iter().any(|b| b.target.same_output(&target)) {
+            combine(target, self.bindings.iter()
+                .filter(|b| b.target.same_output(&target))
+                .map(|b| (b.target, self.read(b.source).map(|value| b.apply(value)))))
+        } else if self.bindings.iter().all(|b| !b.source.same_input(&identity)) {
+            self.read(identity)
+        } else {
+            None
+        }
     }
-    fn get_num_buttons(&self) -> u8 {
-        self.joystick.get_num_buttons()
+
+    /// Get the time the source of a target last changed
+    fn read_time(&self, target: Target, identity: Source) -> Option<Duration> {
+        let time = |source| match source {
+            Source::Button(button) => self.joystick.get_button_time(button),
+            Source::Axis(axis, _) => self.joystick.get_axis_time(axis),
+            _ => None
+        };
+        if self.bindings.iter().any(|b| b.target.same_output(&target)) {
+            self.bindings.iter().filter(|b| b.target.same_output(&target)).filter_map(|b| time(b.source)).max()
+        } else if self.bindings.iter().all(|b| !b.source.same_input(&identity)) {
+            time(identity)
+        } else {
+            None
+        }
     }
-    fn get_battery(&self) -> Option<f32> {
-        self.joystick.get_battery()
+}
+delegate_joystick!(impls JoystickMapper, Joystick);
+impl<J> StatefulJoystick for JoystickMapper<J> where J:StatefulJoystick {
+    fn get_axis(&self, axis: Axis) -> Option<i16> {
+        self.read_target(Target::Axis(axis, AxisRange::Full), Source::Axis(axis, AxisRange::Full))
     }
-    fn poll_native(&mut self) -> Option<<J as Joystick>::NativeEvent> {
-        self.joystick.poll_native()
+    fn get_button(&self, button: Button) -> Option<bool> {
+        self.read_target(Target::Button(button), Source::Button(button)).map(|value| value != 0)
     }
-    fn poll(&mut self) -> Option<Event> {
-        self.joystick.poll().map(|event|
-            match event {
-                Event::ButtonPressed(mut btn) => {
-                    if let Some(&button) = self.buttons.get(&btn) {
-                        btn = button
-                    }
-                    Event::ButtonPressed(btn)
-                },
-                Event::ButtonReleased(mut btn) => {
-                    if let Some(&button) = self.buttons.get(&btn) {
-                        btn = button
-                    }
-                    Event::ButtonReleased(btn)
-                },
-                Event::AxisMoved(mut axis, value) => {
-                    if let Some(&new_axis) = self.axes.get(&axis) {
-                        axis = new_axis
-                    }
-                    Event::AxisMoved(axis, value)
-                },
-            })
+    fn get_axis_time(&self, axis: Axis) -> Option<Duration> {
+        self.read_time(Target::Axis(axis, AxisRange::Full), Source::Axis(axis, AxisRange::Full))
     }
-    fn with_state(self) -> JoystickMapper<<J as Joystick>::WithState> {
-        JoystickMapper {
-            joystick: self.joystick.with_state(),
-            axes: self.axes.clone(),
-            buttons: self.buttons.clone()
+    fn get_button_time(&self, button: Button) -> Option<Duration> {
+        self.read_time(Target::Button(button), Source::Button(button))
+    }
+    /// Hats that are the source of a binding are hidden
+    fn get_hat(&self, index: u8) -> Option<HatDirection> {
+        if self.bindings.iter().any(|b| match b.source { Source::Hat(h, _) => h == index, _ => false }) {
+            None
+        } else {
+            self.joystick.get_hat(index)
         }
     }
+    /// This polls every event so the outputs of the bindings are kept up to date
+    fn update(&mut self) {
+        while let Some(_) = self.poll() {}
+    }
 }
-impl<J> StatefulJoystick for JoystickMapper<J> where J:StatefulJoystick {
-    fn get_axis(&self, mut axis: Axis) -> Option<i16> {
-        use StatefulJoystick;
-        if let Some(&new_axis) = self.axes.get(&axis) {
-            axis = new_axis
+
+/// The name SDL uses for the platform this is compiled for
+#[cfg(target_os = "linux")]
+static PLATFORM: &'static str = "Linux";
+#[cfg(target_os = "windows")]
+static PLATFORM: &'static str = "Windows";
+#[cfg(target_os = "macos")]
+static PLATFORM: &'static str = "Mac OS X";
+#[cfg(target_os = "android")]
+static PLATFORM: &'static str = "Android";
+#[cfg(target_os = "ios")]
+static PLATFORM: &'static str = "iOS";
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos", target_os = "android", target_os = "ios")))]
+static PLATFORM: &'static str = "";
+
+/// Parse a binding from a field of an SDL mapping, like `+leftx:b3` or `dpleft:-a6`,
+/// looking up the sources it numbers on a joystick
+fn parse_binding<J>(joystick: &J, key: &str, value: &str) -> Option<Binding> where J:Joystick {
+    let (key, target_range) = split_range(key);
+    let (value, inverted) = if value.ends_with("~") {
+        (&value[..value.len() - 1], true)
+    } else {
+        (value, false)
+    };
+    let (value, source_range) = split_range(value);
+    let source = if value.starts_with("b") {
+        match value[1..].parse().ok().and_then(|number| joystick.get_mapping_button(number)) {
+            Some(source) => source,
+            None => return None
+        }
+    } else if value.starts_with("a") {
+        match value[1..].parse().ok().and_then(|number| joystick.get_mapping_axis(number)) {
+            Some(Source::Axis(axis, _)) => Source::Axis(axis, source_range),
+            Some(Source::RawAxis(index, _)) => Source::RawAxis(index, source_range),
+            _ => return None
+        }
+    } else if value.starts_with("h") {
+        match value.find('.').map(|dot| (value[1..dot].parse(), value[dot + 1..].parse())) {
+            Some((Ok(hat), Ok(mask))) => Source::Hat(hat, HatDirection::from_mask(mask)),
+            _ => return None
+        }
+    } else {
+        return None
+    };
+    let is_axis = match source {
+        Source::Axis(_, _) | Source::RawAxis(_, _) => true,
+        _ => false
+    };
+    let target = match (sdl_button(key), sdl_axis(key)) {
+        (Some(button), _) if target_range == AxisRange::Full && (!is_axis || sdl_axis(key).is_none()) => Target::Button(button),
+        (_, Some(axis)) => Target::Axis(axis, target_range),
+        (Some(button), None) => Target::Button(button),
+        (None, None) => return None
+    };
+    let mut binding = Binding::new(source, target);
+    binding.inverted = inverted;
+    Some(binding)
+}
+
+/// Split the `+` or `-` off the start of a field of an SDL mapping
+fn split_range(text: &str) -> (&str, AxisRange) {
+    if text.starts_with("+") {
+        (&text[1..], AxisRange::Positive)
+    } else if text.starts_with("-") {
+        (&text[1..], AxisRange::Negative)
+    } else {
+        (text, AxisRange::Full)
+    }
+}
+
+/// Get the prefix of a field of an SDL mapping for a part of an axis
+fn range_prefix(range: AxisRange) -> &'static str {
+    match range {
+        AxisRange::Full => "",
+        AxisRange::Positive => "+",
+        AxisRange::Negative => "-"
+    }
+}
+
+/// Write a binding as a field of an SDL mapping, given the number SDL gives its
+/// source, if it isn't a hat
+fn format_binding(binding: &Binding, number: Option<u8>) -> Option<(String, String)> {
+    let key = match binding.target {
+        Target::Button(button) => sdl_button_name(button).to_string(),
+        Target::Axis(axis, range) => format!("{}{}", range_prefix(range), sdl_axis_name(axis))
+    };
+    let value = match (binding.source, number) {
+        (Source::Button(_), Some(number)) | (Source::RawButton(_), Some(number)) => format!("b{}", number),
+        (Source::Axis(_, range), Some(number)) | (Source::RawAxis(_, range), Some(number)) => format!("{}a{}", range_prefix(range), number),
+        (Source::Hat(hat, direction), _) => format!("h{}.{}", hat, direction.to_mask()),
+        (_, None) => return None
+    };
+    Some((key, if binding.inverted { value + "~" } else { value }))
+}
+
+/// Get the button with the name SDL uses for it
+fn sdl_button(name: &str) -> Option<Button> {
+    Some(match name {
+        "a" => Button::A,
+        "b" => Button::B,
+        "x" => Button::X,
+        "y" => Button::Y,
+        "leftshoulder" => Button::LeftShoulder,
+        "rightshoulder" => Button::RightShoulder,
+        "lefttrigger" => Button::LeftTrigger,
+        "righttrigger" => Button::RightTrigger,
+        "back" => Button::Select,
+        "start" => Button::Start,
+        "leftstick" => Button::LeftStick,
+        "rightstick" => Button::RightStick,
+        "dpup" => Button::DPadUp,
+        "dpdown" => Button::DPadDown,
+        "dpleft" => Button::DPadLeft,
+        "dpright" => Button::DPadRight,
+        _ => return None
+    })
+}
+
+/// Get the name SDL uses for a button
+fn sdl_button_name(button: Button) -> &'static str {
+    match button {
+        Button::A => "a",
+        Button::B => "b",
+        Button::X => "x",
+        Button::Y => "y",
+        Button::LeftShoulder => "leftshoulder",
+        Button::RightShoulder => "rightshoulder",
+        Button::LeftTrigger => "lefttrigger",
+        Button::RightTrigger => "righttrigger",
+        Button::Select => "back",
+        Button::Start => "start",
+        Button::LeftStick => "leftstick",
+        Button::RightStick => "rightstick",
+        Button::DPadUp => "dpup",
+        Button::DPadDown => "dpdown",
+        Button::DPadLeft => "dpleft",
+        Button::DPadRight => "dpright"
+    }
+}
+
+/// Get the axis with the name SDL uses for it
+fn sdl_axis(name: &str) -> Option<Axis> {
+    Some(match name {
+        "leftx" => Axis::LeftX,
+        "lefty" => Axis::LeftY,
+        "rightx" => Axis::RightX,
+        "righty" => Axis::RightY,
+        "lefttrigger" => Axis::TriggerLeft,
+        "righttrigger" => Axis::TriggerRight,
+        _ => return None
+    })
+}
+
+/// Get the name SDL uses for an axis
+fn sdl_axis_name(axis: Axis) -> &'static str {
+    match axis {
+        Axis::LeftX => "leftx",
+        Axis::LeftY => "lefty",
+        Axis::RightX => "rightx",
+        Axis::RightY => "righty",
+        Axis::TriggerLeft => "lefttrigger",
+        Axis::TriggerRight => "righttrigger"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A mapping from SDL's `gamecontrollerdb.txt`, like
+/// `030000005e0400008e02000014010000,X360 Controller,a:b0,leftx:a0,dpup:h0.1,platform:Linux,`
+///
+/// ``` rust
+/// use reminisce::Mapping;
+/// let text = "030000005e0400008e02000014010000,X360 Controller,a:b0,leftx:a0,dpup:h0.1,platform:Linux,";
+/// let mapping: Mapping = text.parse().unwrap();
+/// assert_eq!(mapping.name, "X360 Controller");
+/// assert_eq!(mapping.get("dpup"), Some("h0.1"));
+/// assert_eq!(mapping.get_platform(), Some("Linux"));
+/// assert_eq!(mapping.to_string(), text);
+/// ```
+pub struct Mapping {
+    /// The GUID of the joysticks this mapping is for
+    pub guid: Guid,
+    /// The name of the joystick
+    pub name: String,
+    /// The fields after the name in order, like `("a", "b0")`
+    pub fields: Vec<(String, String)>
+}
+
+impl Mapping {
+    /// Get the value of a field
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref value)| &value[..])
+    }
+
+    /// Get the platform this mapping is for, if it is only for one
+    pub fn get_platform(&self) -> Option<&str> {
+        self.get("platform")
+    }
+
+    /// Check whether this mapping can be used on the platform this is compiled for
+    pub fn is_for_this_platform(&self) -> bool {
+        self.get_platform().map(|platform| platform == PLATFORM).unwrap_or(true)
+    }
+}
+
+impl FromStr for Mapping {
+    type Err = Error;
+    fn from_str(text: &str) -> Result<Mapping, Error> {
+        let mut parts = text.trim().split(',');
+        let guid = match parts.next().and_then(Guid::from_hex) {
+            Some(guid) => guid,
+            None => return Err(Error::InvalidMapping(format!("no GUID in {}", text)))
+        };
+        let name = match parts.next() {
+            Some(name) => name.to_string(),
+            None => return Err(Error::InvalidMapping(format!("no name in {}", text)))
+        };
+        let mut fields = Vec::with_capacity(24);
+        for part in parts.filter(|part| !part.is_empty()) {
+            match part.find(':') {
+                Some(colon) => fields.push((part[..colon].to_string(), part[colon + 1..].to_string())),
+                None => return Err(Error::InvalidMapping(format!("no value for {} in {}", part, text)))
+            }
         }
-        self.joystick.get_axis(axis)
+        Ok(Mapping {
+            guid: guid,
+            name: name,
+            fields: fields
+        })
     }
-    fn get_button(&self, mut button: Button) -> Option<bool> {
-        use StatefulJoystick;
-        if let Some(&btn) = self.buttons.get(&button) {
-            button = btn
+}
+
+impl fmt::Display for Mapping {
+    /// This writes the mapping as a line of `gamecontrollerdb.txt` without a newline
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{},{},", self.guid, self.name));
+        for &(ref key, ref value) in self.fields.iter() {
+            try!(write!(fmt, "{}:{},", key, value));
         }
-        self.joystick.get_button(button)
+        Ok(())
     }
-    fn update(&mut self) {
-        use StatefulJoystick;
-        self.joystick.update()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A collection of SDL mappings, like the community `gamecontrollerdb.txt`
+pub struct MappingDatabase {
+    mappings: Vec<Mapping>
+}
+
+impl MappingDatabase {
+    /// Make an empty database
+    pub fn new() -> MappingDatabase {
+        MappingDatabase {
+            mappings: Vec::new()
+        }
+    }
+
+    /// Parse a database with a mapping on each line
+    ///
+    /// Empty lines and comments starting with `#` are skipped.
+    pub fn parse(text: &str) -> Result<MappingDatabase, Error> {
+        let mut database = MappingDatabase::new();
+        for line in text.lines().map(|line| line.trim()) {
+            if !line.is_empty() && !line.starts_with("#") {
+                database.mappings.push(try!(line.parse()));
+            }
+        }
+        Ok(database)
+    }
+
+    /// Read and parse the database in a file
+    pub fn load<P>(path: P) -> Result<MappingDatabase, Error> where P:AsRef<Path> {
+        use std::fs::File;
+        use std::io::Read;
+        let mut text = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut text));
+        MappingDatabase::parse(&text)
+    }
+
+    /// Add a mapping, replacing the mapping for the same GUID and platform
+    pub fn add(&mut self, mapping: Mapping) {
+        let existing = self.mappings.iter().position(|m| m.guid == mapping.guid && m.get_platform() == mapping.get_platform());
+        match existing {
+            Some(index) => self.mappings[index] = mapping,
+            None => self.mappings.push(mapping)
+        }
+    }
+
+    /// Get the mapping for joysticks with the GUID given on the platform this is compiled for
+    pub fn get(&self, guid: &Guid) -> Option<&Mapping> {
+        self.mappings.iter().find(|mapping| mapping.guid == *guid && mapping.is_for_this_platform())
+    }
+
+    /// Iterate through every mapping, including those for other platforms
+    pub fn iter(&self) -> Iter<Mapping> {
+        self.mappings.iter()
+    }
+
+    /// Get the number of mappings
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+}
+
+impl fmt::Display for MappingDatabase {
+    /// This writes every mapping on its own line
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for mapping in self.mappings.iter() {
+            try!(writeln!(fmt, "{}", mapping));
+        }
+        Ok(())
     }
 }
@@ -9,6 +9,12 @@ extern crate libc;
 #[cfg(feature = "sdl")]
 extern crate sdl2;
 
+#[cfg(feature = "async")]
+extern crate futures;
+
+#[cfg(feature = "async")]
+extern crate tokio;
+
 /// Someday, somehow
 #[cfg(all(feature = "emscripten", not(feature = "sdl")))]
 pub mod emscripten;
@@ -19,9 +25,18 @@ pub use emscripten as native;
 #[cfg(all(target_os = "linux", not(feature = "sdl")))]
 pub mod linux;
 
-#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+#[cfg(all(target_os = "linux", not(feature = "sdl"), not(feature = "evdev")))]
 pub use linux as native;
 
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+pub mod evdev;
+
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+pub mod effect;
+
+#[cfg(all(target_os = "linux", not(feature = "sdl"), feature = "evdev"))]
+pub use evdev as native;
+
 #[cfg(all(target_os = "windows", not(feature = "sdl")))]
 pub mod windows;
 
@@ -34,11 +49,136 @@ pub mod sdl;
 #[cfg(feature = "sdl")]
 pub use sdl as native;
 
+pub mod error;
+
+pub use error::Error;
+
+pub mod info;
+
+pub use info::{BUS_BLUETOOTH, BUS_USB, BUS_VIRTUAL, DeviceInfo, Guid};
+
+pub mod battery;
+
+pub use battery::BatteryState;
+
+/// Forward the parts of a joystick that a wrapper doesn't change to the joystick
+/// it wraps, which is kept in its `joystick` field
+///
+/// `methods` forwards the `Joystick` methods that every wrapper shares and
+/// `mappings` forwards the mapping methods, inside an `impl Joystick`.
+/// `impls Wrapper, Bound` implements `AsRawFd` and `Rumble` for `Wrapper<J>`
+/// where `J` is a `Bound`.
+macro_rules! delegate_joystick(
+	(methods) => (
+		fn is_connected(&self) -> bool {
+			self.joystick.is_connected()
+		}
+		fn get_id(&self) -> ::std::borrow::Cow<str> {
+			self.joystick.get_id()
+		}
+		fn get_info(&self) -> ::DeviceInfo {
+			self.joystick.get_info()
+		}
+		fn get_index(&self) -> u8 {
+			self.joystick.get_index()
+		}
+		fn get_num_axes(&self) -> u8 {
+			self.joystick.get_num_axes()
+		}
+		fn get_num_buttons(&self) -> u8 {
+			self.joystick.get_num_buttons()
+		}
+		fn get_battery(&self) -> Option<f32> {
+			self.joystick.get_battery()
+		}
+		fn get_battery_state(&self) -> Option<::BatteryState> {
+			self.joystick.get_battery_state()
+		}
+		fn try_poll_native(&mut self) -> Result<Option<<J as ::Joystick>::NativeEvent>, ::Error> {
+			self.joystick.try_poll_native()
+		}
+		fn get_event_time(event: &<J as ::Joystick>::NativeEvent) -> ::std::time::Duration {
+			J::get_event_time(event)
+		}
+	);
+	(mappings) => (
+		#[cfg(feature = "mappings")]
+		fn get_mapping_button(&self, number: u8) -> Option<::mapper::Source> {
+			self.joystick.get_mapping_button(number)
+		}
+		#[cfg(feature = "mappings")]
+		fn get_mapping_axis(&self, number: u8) -> Option<::mapper::Source> {
+			self.joystick.get_mapping_axis(number)
+		}
+	);
+	(impls $wrapper:ident, $bound:ident) => (
+		#[cfg(unix)]
+		impl<J> ::std::os::unix::io::AsRawFd for $wrapper<J> where J: ::$bound+::std::os::unix::io::AsRawFd {
+			fn as_raw_fd(&self) -> ::std::os::unix::io::RawFd {
+				self.joystick.as_raw_fd()
+			}
+		}
+
+		impl<J> ::Rumble for $wrapper<J> where J: ::$bound+::Rumble {
+			fn supports_rumble(&self) -> bool {
+				self.joystick.supports_rumble()
+			}
+			fn rumble(&mut self, strong: f32, weak: f32, duration: ::std::time::Duration) -> Result<(), ::Error> {
+				self.joystick.rumble(strong, weak, duration)
+			}
+		}
+	)
+);
+
+pub mod hat;
+
+pub use hat::DPadFromHat;
+
+pub mod deadzone;
+
+pub use deadzone::{DeadZoneJoystick, DeadZones, StickMode};
+
+pub mod curve;
+
+pub use curve::{Curve, CurveJoystick, Curves};
+
+pub mod filter;
+
+pub use filter::{Filter, FilterJoystick, Filters};
+
+pub mod snapshot;
+
+pub use snapshot::{Snapshot, SnapshotJoystick};
+
+pub mod actions;
+
+pub mod wait;
+
+pub use wait::{wait, wait_timed, Waitable, Waiter};
+
+pub mod hub;
+
+pub mod virtual_joystick;
+
+pub use virtual_joystick::{VirtualController, VirtualJoystick};
+
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+pub mod manager;
+
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+pub use manager::{DeviceJoystick, JoystickManager, ManagerEvent};
+
+#[cfg(all(feature = "async", target_os = "linux", not(feature = "sdl")))]
+pub mod stream;
+
+#[cfg(all(feature = "async", target_os = "linux", not(feature = "sdl")))]
+pub use stream::{EventStream, ManagerStream};
+
 #[cfg(feature = "mappings")]
 pub mod mapper;
 
 #[cfg(feature = "mappings")]
-pub use mapper::JoystickMapper;
+pub use mapper::{JoystickMapper, Mapping, MappingDatabase};
 pub use native::{NativeJoystick, scan};
 
 
@@ -46,13 +186,15 @@ pub use native::{NativeJoystick, scan};
 pub static MAX_AXIS_VALUE:i16 = 32767;
 /// The minimum axis value
 pub static MIN_AXIS_VALUE:i16 = -32767;
+/// The number of axes that have an `Axis`
+pub static NUM_AXES:u8 = 6;
+/// The number of buttons that have a `Button`
+pub static NUM_BUTTONS:u8 = 16;
 
 use std::borrow::Cow;
-use std::error::Error;
-use std::fmt::Debug;
+use std::time::Duration;
 use std::mem::transmute as cast;
 
-#[cfg(feature = "mappings")]
 macro_rules! text_enum(
     ($name:ident, $($enumer:ident => $text:expr),+) => (
         impl ::std::fmt::Display for $name {
@@ -104,7 +246,16 @@ pub enum Axis {
     /// This is only used as a button on some platforms so don't rely on just this
     TriggerRight
 }
-#[cfg(feature = "mappings")]
+impl Axis {
+    /// Get the axis with the given index, if there is one
+    pub fn from_index(index: u8) -> Option<Axis> {
+        if index < NUM_AXES {
+            Some(unsafe { cast(index) })
+        } else {
+            None
+        }
+    }
+}
 text_enum!(Axis,
     LeftX => "leftx",
     LeftY => "lefty",
@@ -167,7 +318,16 @@ pub enum Button {
 	/// The right button on the directional pad
 	DPadRight
 }
-#[cfg(feature = "mappings")]
+impl Button {
+    /// Get the button with the given index, if there is one
+    pub fn from_index(index: u8) -> Option<Button> {
+        if index < NUM_BUTTONS {
+            Some(unsafe { cast(index) })
+        } else {
+            None
+        }
+    }
+}
 text_enum!(Button,
     A => "a",
     B => "b",
@@ -187,6 +347,101 @@ text_enum!(Button,
     DPadRight => "dpadright"
 );
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The direction a hat switch, also known as a POV switch, is pushed in
+pub enum HatDirection {
+	/// The hat isn't pushed in any direction
+	Centred,
+	/// Up
+	Up,
+	/// Up and right
+	UpRight,
+	/// Right
+	Right,
+	/// Down and right
+	DownRight,
+	/// Down
+	Down,
+	/// Down and left
+	DownLeft,
+	/// Left
+	Left,
+	/// Up and left
+	UpLeft
+}
+impl HatDirection {
+    /// Get the direction from the horizontal and vertical positions of the hat,
+    /// where negative values are left and up
+    pub fn from_axes(x: i16, y: i16) -> HatDirection {
+        let vertical = if y < 0 { 1 } else if y > 0 { 4 } else { 0 };
+        let horizontal = if x > 0 { 2 } else if x < 0 { 8 } else { 0 };
+        HatDirection::from_mask(vertical | horizontal)
+    }
+
+    /// Get the horizontal and vertical positions of the hat, which are -1, 0 or 1
+    pub fn to_axes(self) -> (i8, i8) {
+        let mask = self.to_mask();
+        let x = if mask & 2 != 0 { 1 } else if mask & 8 != 0 { -1 } else { 0 };
+        let y = if mask & 4 != 0 { 1 } else if mask & 1 != 0 { -1 } else { 0 };
+        (x, y)
+    }
+
+    /// Get the direction from a mask of bits, where up is 1, right is 2, down is 4
+    /// and left is 8, like in SDL
+    ///
+    /// Masks with opposite directions in them are centred.
+    pub fn from_mask(mask: u8) -> HatDirection {
+        match mask & 0xF {
+            1 => HatDirection::Up,
+            3 => HatDirection::UpRight,
+            2 => HatDirection::Right,
+            6 => HatDirection::DownRight,
+            4 => HatDirection::Down,
+            12 => HatDirection::DownLeft,
+            8 => HatDirection::Left,
+            9 => HatDirection::UpLeft,
+            _ => HatDirection::Centred
+        }
+    }
+
+    /// Get the mask of bits for the direction, as used by `from_mask`
+    pub fn to_mask(self) -> u8 {
+        match self {
+            HatDirection::Centred => 0,
+            HatDirection::Up => 1,
+            HatDirection::UpRight => 3,
+            HatDirection::Right => 2,
+            HatDirection::DownRight => 6,
+            HatDirection::Down => 4,
+            HatDirection::DownLeft => 12,
+            HatDirection::Left => 8,
+            HatDirection::UpLeft => 9
+        }
+    }
+
+    /// Check whether this direction includes another direction, like `UpLeft` includes `Up`
+    pub fn contains(self, other: HatDirection) -> bool {
+        other != HatDirection::Centred && self.to_mask() & other.to_mask() == other.to_mask()
+    }
+
+    /// Check whether a directional pad button would be held when the hat is pushed this way
+    ///
+    /// ``` rust
+    /// use reminisce::{Button, HatDirection};
+    /// assert!(HatDirection::UpLeft.is_pressed(Button::DPadLeft));
+    /// assert!(!HatDirection::UpLeft.is_pressed(Button::DPadDown));
+    /// ```
+    pub fn is_pressed(self, button: Button) -> bool {
+        match button {
+            Button::DPadUp => self.contains(HatDirection::Up),
+            Button::DPadDown => self.contains(HatDirection::Down),
+            Button::DPadLeft => self.contains(HatDirection::Left),
+            Button::DPadRight => self.contains(HatDirection::Right),
+            _ => false
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 /// An event emitted by a joystick
 pub enum Event {
@@ -196,13 +451,60 @@ pub enum Event {
 	ButtonReleased(Button),
 	/// Fired when a axis is moved with the axis index and its value,
 	/// which is between `MIN_JOYSTICK_VALUE` and `MAX_JOYSTICK_VALUE`
-	AxisMoved(Axis, i16)
+	AxisMoved(Axis, i16),
+	/// Fired when an axis that doesn't have an `Axis` is moved with the index
+	/// the backend reports it with and its value
+	RawAxisMoved(u8, i16),
+	/// Fired when a button that doesn't have a `Button` is pressed or released
+	/// with the index the backend reports it with and whether it is pressed
+	RawButton(u8, bool),
+	/// Fired with the value of an axis when the joystick is opened, if the
+	/// backend reports it and this has been enabled
+	InitialAxis(Axis, i16),
+	/// Fired with whether a button is held when the joystick is opened, if the
+	/// backend reports it and this has been enabled
+	///
+	/// This shouldn't be treated as the button being pressed.
+	InitialButton(Button, bool),
+	/// Fired when a hat switch is moved with the hat's index and the direction
+	/// it is now pushed in
+	///
+	/// Use `Joystick::dpad_from_hat` to get directional pad button events for hats too.
+	HatMoved(u8, HatDirection)
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// An event along with when it was emitted and which joystick emitted it
+pub struct TimedEvent {
+	/// The event
+	pub event: Event,
+	/// When the event was emitted by the backend
+	///
+	/// This is relative to an arbitrary point in time, like when the system booted, so
+	/// it should only be compared against times of other events from the same backend.
+	pub time: Duration,
+	/// The index of the joystick that emitted the event
+	pub source: u8
 }
+
+#[cfg(not(all(target_os = "linux", not(feature = "sdl"))))]
 impl From<<NativeJoystick as Joystick>::NativeEvent> for Event {
 	fn from(event: <NativeJoystick as Joystick>::NativeEvent) -> Event {
         native::convert_event(event)
 	}
 }
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+impl From<linux::LinuxEvent> for Event {
+	fn from(event: linux::LinuxEvent) -> Event {
+        linux::convert_event(event)
+	}
+}
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+impl From<evdev::InputEvent> for Event {
+	fn from(event: evdev::InputEvent) -> Event {
+        evdev::convert_event(event)
+	}
+}
 
 /// A joystick or gamepad
 ///
@@ -210,8 +512,10 @@ impl From<<NativeJoystick as Joystick>::NativeEvent> for Event {
 /// using the `iter()` method or by calling the `poll()` method repeatedly.
 ///
 /// ``` rust
-/// use reminisce::{scan, Axis, Event, Joystick};
-/// let mut joysticks = scan();
+/// use reminisce::{Axis, Event, Joystick, VirtualJoystick};
+/// let mut joysticks = vec![VirtualJoystick::new(0, "Left"), VirtualJoystick::new(1, "Right")];
+/// joysticks[0].controller().move_axis(Axis::LeftX, 100);
+/// joysticks[1].controller().move_axis(Axis::LeftY, -200);
 /// let (mut x, mut y) = (0, 0);
 /// for joystick in &mut joysticks {
 ///     for event in joystick.iter() {
@@ -224,7 +528,7 @@ impl From<<NativeJoystick as Joystick>::NativeEvent> for Event {
 ///         }
 ///     }
 /// }
-/// println!("{}, {}", x, y);
+/// assert_eq!((x, y), (100, -200));
 /// ```
 pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
 	/// The version of this joystick that includes the state
@@ -237,12 +541,9 @@ pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
     /// The event that this joystick processes
     type NativeEvent;
 
-    /// The error that could be thrown while trying to open the joystick
-    type OpenError: Error + Debug;
-
 	/// Attempts to open a joystick from its index
     ///
-    /// If an error occurs, this will return the textual representation of that error.
+    /// If an error occurs, this will return the `Error` that caused it.
     ///
     /// ``` rust
     /// use reminisce::{NativeJoystick, Joystick};
@@ -252,7 +553,7 @@ pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
     ///     println!("No joystick plugged in")
     /// }
     /// ```
-	fn open(index: u8) -> Result<Self, Self::OpenError>;
+	fn open(index: u8) -> Result<Self, Error>;
 
 	/// Check if the joystick is still connected
 	fn is_connected(&self) -> bool;
@@ -263,17 +564,28 @@ pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
     /// implementation.
 	fn get_id(&self) -> Cow<str>;
 
+	/// Get information identifying this joystick, like its vendor id, product id and GUID
+	///
+	/// By default, only the name is known and the GUID is computed from it.
+	fn get_info(&self) -> DeviceInfo {
+		DeviceInfo::from_name(self.get_id().into_owned())
+	}
+
 	/// Get the index of this joystick
 	fn get_index(&self) -> u8;
 
 	/// Get the number of axes this joystick has
     ///
-    /// This is capped at 6 axes for now.
+    /// This counts every axis the backend reports, not only those with an `Axis`.
+    /// Axes past the ones with an `Axis` are exposed as raw axes, through
+    /// `Event::RawAxisMoved` and `StatefulJoystick::get_raw_axis`.
 	fn get_num_axes(&self) -> u8;
 
 	/// Get the number of buttons this joystick has
     ///
-    /// This is capped at 16 buttons currently.
+    /// This counts every button the backend reports, not only those with a `Button`.
+    /// Buttons past the ones with a `Button` are exposed as raw buttons, through
+    /// `Event::RawButton` and `StatefulJoystick::get_raw_button`.
 	fn get_num_buttons(&self) -> u8;
 
     /// Get the battery level of this joystick
@@ -282,16 +594,89 @@ pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
     /// by the backend
     fn get_battery(&self) -> Option<f32>;
 
+    /// Get what the battery of this joystick is doing, like charging
+    ///
+    /// Returns none if this isn't known or this operation is not supported by the backend
+    fn get_battery_state(&self) -> Option<BatteryState> {
+        None
+    }
+
+    /// Poll the joystick for events in non-blocking mode and return the native event
+    /// as returned from the backend, or the error that occurred while polling
+	fn try_poll_native(&mut self) -> Result<Option<Self::NativeEvent>, Error>;
+
+    /// Get the time a native event was emitted by the backend
+    fn get_event_time(event: &Self::NativeEvent) -> Duration;
+
     /// Poll the joystick for events in non-blocking mode and return the native event
     /// as returned from the backend
-	fn poll_native(&mut self) -> Option<Self::NativeEvent>;
+    ///
+    /// This runs `self.try_poll_native()` and ignores any error that occurs.
+	fn poll_native(&mut self) -> Option<Self::NativeEvent> {
+        self.try_poll_native().unwrap_or(None)
+    }
+
+    /// Poll the joystick for events in non-blocking mode along with the time they were
+    /// emitted, or return the error that occurred while polling
+    ///
+    /// This runs `self.try_poll_native()` then converts the event using `Event::from`.
+    ///
+    /// ``` rust
+    /// use reminisce::{scan, Button, Event, Joystick};
+    /// for mut joystick in scan() {
+    ///     let mut last_press = None;
+    ///     while let Some(timed) = joystick.poll_timed() {
+    ///         if let Event::ButtonPressed(Button::A) = timed.event {
+    ///             if let Some(last) = last_press {
+    ///                 println!("A pressed again after {:?}", timed.time - last);
+    ///             }
+    ///             last_press = Some(timed.time);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+        let source = self.get_index();
+        self.try_poll_native().map(|e| e.map(|e| TimedEvent {
+            time: Self::get_event_time(&e),
+            event: e.into(),
+            source: source
+        }))
+    }
+
+    /// Poll the joystick for events in non-blocking mode along with the time they were
+    /// emitted
+    ///
+    /// This runs `self.try_poll_timed()` and ignores any error that occurs.
+    fn poll_timed(&mut self) -> Option<TimedEvent> {
+        self.try_poll_timed().unwrap_or(None)
+    }
+
+    /// Poll the joystick for events in non-blocking mode, or return the error that
+    /// occurred while polling
+    ///
+    /// This runs `self.try_poll_timed()` then discards the time.
+    ///
+    /// ``` rust
+    /// use reminisce::{scan, Error, Joystick};
+    /// for mut joystick in scan() {
+    ///     match joystick.try_poll() {
+    ///         Ok(Some(event)) => println!("{:?}", event),
+    ///         Ok(None) => (),
+    ///         Err(Error::Disconnected) => println!("Joystick #{} unplugged", joystick.get_index()),
+    ///         Err(err) => println!("{}", err)
+    ///     }
+    /// }
+    /// ```
+    fn try_poll(&mut self) -> Result<Option<Event>, Error> {
+        self.try_poll_timed().map(|e| e.map(|e| e.event))
+    }
 
     /// Poll the joystick for events in non-blocking mode
     ///
-    /// This runs `self.poll_native()` then converts it into an event using
-    /// the `into_event` method.
+    /// This runs `self.try_poll()` and ignores any error that occurs.
     fn poll(&mut self) -> Option<Event> {
-        self.poll_native().map(|e| e.into())
+        self.try_poll().unwrap_or(None)
     }
 
     /// Map the axes and buttons of this joystick by wrapping it in a `JoystickMapper`
@@ -300,6 +685,46 @@ pub trait Joystick : Sized where Event: From<Self::NativeEvent> {
         JoystickMapper::new(self)
     }
 
+    /// Get the button that SDL mappings mean by `b` followed by `number`, which is
+    /// the number SDL gives the button on this platform
+    ///
+    /// This is `None` if the joystick doesn't have the button. By default buttons are
+    /// numbered in the order of `Button`, followed by the raw buttons.
+    #[cfg(feature = "mappings")]
+    fn get_mapping_button(&self, number: u8) -> Option<mapper::Source> {
+        Some(Button::from_index(number).map(mapper::Source::Button).unwrap_or(mapper::Source::RawButton(number)))
+    }
+
+    /// Get the axis that SDL mappings mean by `a` followed by `number`, which is
+    /// the number SDL gives the axis on this platform
+    ///
+    /// The axis covers its whole range. This is `None` if the joystick doesn't have
+    /// the axis. By default axes are numbered in the order of `Axis`, followed by the
+    /// raw axes.
+    #[cfg(feature = "mappings")]
+    fn get_mapping_axis(&self, number: u8) -> Option<mapper::Source> {
+        let full = mapper::AxisRange::Full;
+        Some(Axis::from_index(number).map(|axis| mapper::Source::Axis(axis, full)).unwrap_or(mapper::Source::RawAxis(number, full)))
+    }
+
+    /// Report the first hat switch as the directional pad buttons as well, by wrapping
+    /// this in a `DPadFromHat`
+    fn dpad_from_hat(self) -> DPadFromHat<Self> {
+        DPadFromHat::new(self)
+    }
+
+    /// Apply response curves to the axes of this joystick by wrapping it in a
+    /// `CurveJoystick`
+    fn with_curves(self, curves: Curves) -> CurveJoystick<Self> {
+        CurveJoystick::new(self, curves)
+    }
+
+    /// Smooth the axes of this joystick or drop small changes to them by wrapping
+    /// it in a `FilterJoystick`
+    fn with_filters(self, filters: Filters) -> FilterJoystick<Self> {
+        FilterJoystick::new(self, filters)
+    }
+
     /// Iterate through the events that haven't been processed yet
     fn iter(&mut self) -> Poller<Self> {
         Poller {
@@ -364,8 +789,86 @@ pub trait StatefulJoystick : Joystick + Sized {
 	/// Get the pressed state of a specific button
 	fn get_button(&self, index: Button) -> Option<bool>;
 
+	/// Get the value of an axis from the index the backend reports it with
+    ///
+    /// This includes axes that don't have an `Axis`, like the throttle on a flight stick.
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		Axis::from_index(index).and_then(|axis| self.get_axis(axis))
+	}
+
+	/// Get the pressed state of a button from the index the backend reports it with
+    ///
+    /// This includes buttons that don't have a `Button`.
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		Button::from_index(index).and_then(|button| self.get_button(button))
+	}
+
+	/// Get the time the value of a specific axis last changed
+    ///
+    /// This returns None if the axis hasn't changed since the joystick was opened
+    /// or the backend doesn't record it.
+	fn get_axis_time(&self, _index: Axis) -> Option<Duration> {
+		None
+	}
+
+	/// Get the time a specific button was last pressed or released
+    ///
+    /// This returns None if the button hasn't changed since the joystick was opened
+    /// or the backend doesn't record it.
+	fn get_button_time(&self, _index: Button) -> Option<Duration> {
+		None
+	}
+
+	/// Get the direction a hat switch is pushed in from its index
+	///
+	/// This returns None if the joystick doesn't have the hat or the backend
+	/// doesn't report hats.
+	fn get_hat(&self, _index: u8) -> Option<HatDirection> {
+		None
+	}
+
 	/// Update the state of this joystick by polling the native backend
 	fn update(&mut self);
+
+	/// Apply dead zones to the sticks and triggers of this joystick by wrapping it
+	/// in a `DeadZoneJoystick`
+	fn with_dead_zones(self, zones: DeadZones) -> DeadZoneJoystick<Self> {
+		DeadZoneJoystick::new(self, zones)
+	}
+
+	/// Capture a snapshot of this joystick every time it is updated, to find which
+	/// buttons were just pressed, by wrapping it in a `SnapshotJoystick`
+	fn with_snapshots(self) -> SnapshotJoystick<Self> {
+		SnapshotJoystick::new(self)
+	}
+}
+
+/// A joystick with motors that can make it rumble
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::{scan, Rumble};
+/// for mut js in scan() {
+///     if js.supports_rumble() {
+///         js.rumble(1.0, 0.5, Duration::from_millis(200)).unwrap();
+///     }
+/// }
+/// ```
+pub trait Rumble {
+	/// Check whether this joystick can rumble
+	fn supports_rumble(&self) -> bool;
+
+	/// Rumble the strong, low frequency motor and the weak, high frequency motor
+	/// for a duration, replacing any rumble that is already playing
+	///
+	/// The magnitudes are between 0 and 1. This returns `Error::Unsupported` if the
+	/// joystick can't rumble.
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error>;
+
+	/// Stop any rumble that is playing
+	fn stop_rumble(&mut self) -> Result<(), Error> {
+		self.rumble(0.0, 0.0, Duration::new(0, 0))
+	}
 }
 
 /// An iterator over a joystick's axes
@@ -378,8 +881,8 @@ impl<'a, J> Iterator for Axes<'a, J> where J:StatefulJoystick {
 	type Item = (Axis, i16);
 	fn next(&mut self) -> Option<(Axis, i16)> {
 		self.axis += 1;
-		let axis = unsafe { cast(self.axis - 1) };
-		self.joystick.get_axis(axis).map(|v| (axis, v))
+		let joystick = self.joystick;
+		Axis::from_index(self.axis - 1).and_then(|axis| joystick.get_axis(axis).map(|v| (axis, v)))
 	}
 }
 
@@ -393,8 +896,8 @@ impl<'a, J> Iterator for Buttons<'a, J> where J:StatefulJoystick {
 	type Item = (Button, bool);
 	fn next(&mut self) -> Option<(Button, bool)> {
 		self.button += 1;
-		let button = unsafe { cast(self.button - 1) };
-		self.joystick.get_button(button).map(|v| (button, v))
+		let joystick = self.joystick;
+		Button::from_index(self.button - 1).and_then(|button| joystick.get_button(button).map(|v| (button, v)))
 	}
 }
 
@@ -3,9 +3,8 @@ use sdl2::event::Event;
 use sdl2::{init, Sdl, INIT_GAME_CONTROLLER, INIT_EVENTS};
 
 use std::borrow::Cow;
-use std::error::Error;
-use std::fmt;
 use std::rc::Rc;
+use std::time::Duration;
 
 /// A native joystick using SDL
 pub struct NativeJoystick {
@@ -22,22 +21,48 @@ impl NativeJoystick {
 }
 
 /// Convert the SDL event into a Reminisce event
+///
+/// Axes and buttons with indices that don't have an `Axis` or `Button` are
+/// converted into raw events. Only joystick axis, button and hat events are
+/// returned by `try_poll_native`, so other events are converted into the first
+/// hat being centred instead of panicking.
 pub fn convert_event(event: Event) -> ::Event {
-    use std::mem::transmute as cast;
     match event {
         Event::JoyAxisMotion {axis_idx, value, ..} => {
-            let index = unsafe { cast(axis_idx) };
-            ::Event::AxisMoved(index, value)
+            match ::Axis::from_index(axis_idx) {
+                Some(axis) => ::Event::AxisMoved(axis, value),
+                None => ::Event::RawAxisMoved(axis_idx, value)
+            }
         },
         Event::JoyButtonDown {button_idx, ..} => {
-            let index = unsafe { cast(button_idx) };
-            ::Event::ButtonPressed(index)
+            match ::Button::from_index(button_idx) {
+                Some(button) => ::Event::ButtonPressed(button),
+                None => ::Event::RawButton(button_idx, true)
+            }
         },
         Event::JoyButtonUp {button_idx, ..} => {
-            let index = unsafe { cast(button_idx) };
-            ::Event::ButtonReleased(index)
+            match ::Button::from_index(button_idx) {
+                Some(button) => ::Event::ButtonReleased(button),
+                None => ::Event::RawButton(button_idx, false)
+            }
         },
-        _ => unimplemented!()
+        Event::JoyHatMotion {hat_idx, state, ..} => ::Event::HatMoved(hat_idx, convert_hat(state)),
+        // `try_poll_native` never returns other events, but they mustn't panic
+        _ => ::Event::HatMoved(0, ::HatDirection::Centred)
+    }
+}
+/// Convert the SDL hat state into a Reminisce hat direction
+pub fn convert_hat(state: HatState) -> ::HatDirection {
+    match state {
+        HatState::Centered => ::HatDirection::Centred,
+        HatState::Up => ::HatDirection::Up,
+        HatState::RightUp => ::HatDirection::UpRight,
+        HatState::Right => ::HatDirection::Right,
+        HatState::RightDown => ::HatDirection::DownRight,
+        HatState::Down => ::HatDirection::Down,
+        HatState::LeftDown => ::HatDirection::DownLeft,
+        HatState::Left => ::HatDirection::Left,
+        HatState::LeftUp => ::HatDirection::UpLeft
     }
 }
 /// Scan for joysticks and initialise SDL
@@ -48,36 +73,13 @@ pub fn scan() -> Vec<NativeJoystick> {
     (0..num).filter_map(|i| ::Joystick::open(i).ok().map(|js:NativeJoystick| js.in_context(sdl.clone()))).collect()
 }
 
-pub struct OpenError {
-    err: String
-}
-impl Error for OpenError {
-    fn description(&self) -> &str {
-        &self.err
-    }
-    fn cause(&self) -> Option<&Error> {
-        None
-    }
-}
-impl fmt::Debug for OpenError {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.err)
-    }
-}
-impl fmt::Display for OpenError {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.err)
-    }
-}
-
 impl ::Joystick for NativeJoystick {
     type WithState = NativeJoystick;
     type NativeEvent = Event;
-    type OpenError = OpenError;
-    fn open(index: u8) -> Result<NativeJoystick, OpenError> {
+    fn open(index: u8) -> Result<NativeJoystick, ::Error> {
         match Joystick::open(index as i32) {
             Ok(js) => Ok(NativeJoystick { js: js, sdl: None }),
-            Err(err) => Err(OpenError { err: err })
+            Err(err) => Err(::Error::Backend(err))
         }
     }
     fn is_connected(&self) -> bool {
@@ -89,6 +91,14 @@ impl ::Joystick for NativeJoystick {
     fn get_id(&self) -> Cow<str> {
         self.js.name().into()
     }
+    /// SDL only knows the name and GUID of the joystick
+    fn get_info(&self) -> ::DeviceInfo {
+        let mut info = ::DeviceInfo::from_name(self.js.name());
+        if let Some(guid) = ::Guid::from_hex(&self.js.get_guid().to_string()) {
+            info.guid = guid;
+        }
+        info
+    }
     fn get_num_buttons(&self) -> u8 {
         self.js.get_num_buttons().unwrap() as u8
     }
@@ -98,28 +108,55 @@ impl ::Joystick for NativeJoystick {
     fn get_battery(&self) -> Option<f32> {
         None
     }
-    fn poll_native(&mut self) -> Option<Event> {
+    fn try_poll_native(&mut self) -> Result<Option<Event>, ::Error> {
         if self.sdl.is_none() {
             let flags = INIT_GAME_CONTROLLER | INIT_EVENTS;
-            self.sdl = Some(Rc::new(init(flags).unwrap()))
+            self.sdl = Some(Rc::new(try!(init(flags).map_err(::Error::Backend))))
         }
         let sdl = self.sdl.clone().unwrap();
         let mut pump = sdl.event_pump();
         for event in pump.poll_iter() {
             match event {
-                Event::JoyAxisMotion{ .. } | Event::JoyButtonDown{ .. } | Event::JoyButtonUp{ .. } => {
-                    return Some(event)
+                Event::JoyAxisMotion{ .. } | Event::JoyButtonDown{ .. } | Event::JoyButtonUp{ .. } |
+                Event::JoyHatMotion{ .. } => {
+                    return Ok(Some(event))
                 },
                 _ => ()
             }
         }
-        None
+        if self.js.get_attached() {
+            Ok(None)
+        } else {
+            Err(::Error::Disconnected)
+        }
+    }
+    /// SDL timestamps events in milliseconds since it was initialised
+    fn get_event_time(event: &Event) -> Duration {
+        let timestamp = match *event {
+            Event::JoyAxisMotion {timestamp, ..} | Event::JoyButtonDown {timestamp, ..} |
+            Event::JoyButtonUp {timestamp, ..} | Event::JoyHatMotion {timestamp, ..} => timestamp,
+            _ => 0
+        };
+        Duration::from_millis(timestamp as u64)
     }
     fn with_state(self) -> NativeJoystick {
         self
     }
 }
 
+impl ::Rumble for NativeJoystick {
+    fn supports_rumble(&self) -> bool {
+        self.js.has_rumble()
+    }
+    /// This uses `SDL_JoystickRumble`
+    fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), ::Error> {
+        let millis = duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1000000;
+        let magnitude = |value: f32| (value.max(0.0).min(1.0) * 65535.0) as u16;
+        self.js.set_rumble(magnitude(strong), magnitude(weak), millis.min(0xFFFFFFFF) as u32)
+            .map_err(|err| ::Error::Backend(err.to_string()))
+    }
+}
+
 impl ::StatefulJoystick for NativeJoystick {
     fn get_axis(&self, axis: ::Axis) -> Option<i16> {
         self.js.get_axis(axis as u8 as i32).ok()
@@ -127,6 +164,15 @@ impl ::StatefulJoystick for NativeJoystick {
     fn get_button(&self, button: ::Button) -> Option<bool> {
         self.js.get_button(button as u8 as i32).ok()
     }
+    fn get_raw_axis(&self, index: u8) -> Option<i16> {
+        self.js.get_axis(index as i32).ok()
+    }
+    fn get_raw_button(&self, index: u8) -> Option<bool> {
+        self.js.get_button(index as i32).ok()
+    }
+    fn get_hat(&self, index: u8) -> Option<::HatDirection> {
+        self.js.get_hat(index as i32).ok().map(convert_hat)
+    }
     fn update(&mut self) {
         update();
     }
@@ -0,0 +1,290 @@
+use {Axis, Button, Error, Event, HatDirection, Joystick, StatefulJoystick, TimedEvent};
+use {MIN_AXIS_VALUE, NUM_AXES, NUM_BUTTONS};
+use std::mem;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq)]
+/// The axes and buttons of a joystick at the end of a frame, with the presses
+/// and releases that happened during it
+pub struct Snapshot {
+	axes: Vec<i16>,
+	buttons: u32,
+	pressed: u32,
+	released: u32,
+	held_since: Vec<Option<Instant>>,
+	time: Instant
+}
+
+impl Snapshot {
+	/// Capture the state of a joystick
+	///
+	/// Triggers the joystick doesn't have are at rest, which is `MIN_AXIS_VALUE`.
+	fn capture<J>(joystick: &J, pressed: u32, released: u32, held_since: Vec<Option<Instant>>, time: Instant) -> Snapshot where J:StatefulJoystick {
+		let axes = (0..NUM_AXES).filter_map(Axis::from_index).map(|axis| {
+			joystick.get_axis(axis).unwrap_or(match axis {
+				Axis::TriggerLeft | Axis::TriggerRight => MIN_AXIS_VALUE,
+				_ => 0
+			})
+		}).collect();
+		let buttons = (0..NUM_BUTTONS).fold(0, |buttons, index| {
+			let pressed = Button::from_index(index).and_then(|button| joystick.get_button(button)).unwrap_or(false);
+			if pressed { buttons | 1 << index } else { buttons }
+		});
+		Snapshot {
+			axes: axes,
+			buttons: buttons,
+			pressed: pressed,
+			released: released,
+			held_since: held_since,
+			time: time
+		}
+	}
+
+	/// Get the value of an axis
+	pub fn get_axis(&self, axis: Axis) -> i16 {
+		self.axes[axis as usize]
+	}
+
+	/// Check if a button is pressed
+	pub fn get_button(&self, button: Button) -> bool {
+		self.buttons & 1 << button as u8 != 0
+	}
+
+	/// Check if a button was pressed during the frame, even if it was released again
+	pub fn was_pressed(&self, button: Button) -> bool {
+		self.pressed & 1 << button as u8 != 0
+	}
+
+	/// Check if a button was released during the frame, even if it was pressed again
+	pub fn was_released(&self, button: Button) -> bool {
+		self.released & 1 << button as u8 != 0
+	}
+
+	/// Get how long a button had been held for when this was captured, or zero if
+	/// it wasn't pressed
+	///
+	/// This is measured from the press to the time this was captured, so it keeps
+	/// growing while the button is held even if nothing else happens.
+	pub fn held_for(&self, button: Button) -> Duration {
+		match self.held_since[button as usize] {
+			Some(since) if self.get_button(button) && self.time > since => self.time.duration_since(since),
+			_ => Duration::from_millis(0)
+		}
+	}
+
+	/// Get the time this was captured
+	pub fn get_time(&self) -> Instant {
+		self.time
+	}
+}
+
+/// A joystick that captures a `Snapshot` of its axes and buttons every time it
+/// is updated, and keeps the one before it
+///
+/// Every press and release is recorded as its event is polled, so a button that
+/// is pressed and released between two updates is still reported as just pressed
+/// and just released. The state of the axes and buttons doesn't change between
+/// updates, so every part of a game sees the same frame.
+///
+/// The time of a press is worked out from the time of the last event polled and
+/// how long ago it was polled, so presses polled together keep the spacing the
+/// backend gave them.
+///
+/// ``` rust
+/// use std::thread;
+/// use std::time::Duration;
+/// use reminisce::{Axis, Button, StatefulJoystick, VirtualJoystick, MIN_AXIS_VALUE};
+/// let js = VirtualJoystick::new(0, "Game Pad");
+/// let controller = js.controller();
+/// let mut js = js.with_snapshots();
+/// // A tap that is over before the frame ends
+/// controller.press(Button::A);
+/// controller.release(Button::A);
+/// controller.press(Button::B);
+/// controller.move_axis(Axis::LeftX, 1000);
+/// js.update();
+/// assert!(js.just_pressed(Button::A) && js.just_released(Button::A));
+/// assert_eq!(js.get_button(Button::A), Some(false));
+/// assert!(js.just_pressed(Button::B) && !js.just_released(Button::B));
+/// assert_eq!(js.axis_delta(Axis::LeftX), 1000);
+/// thread::sleep(Duration::from_millis(20));
+/// controller.move_axis(Axis::LeftX, -1000);
+/// js.update();
+/// assert!(!js.just_pressed(Button::A) && !js.just_pressed(Button::B));
+/// assert!(js.held_for(Button::B) >= Duration::from_millis(20));
+/// assert_eq!(js.held_for(Button::A), Duration::from_millis(0));
+/// assert_eq!(js.axis_delta(Axis::LeftX), -2000);
+/// // Holding the button with nothing else happening
+/// let before = js.held_for(Button::B);
+/// thread::sleep(Duration::from_millis(20));
+/// js.update();
+/// assert!(js.held_for(Button::B) >= before + Duration::from_millis(20));
+///
+/// // A joystick without triggers has them at rest
+/// let pad = VirtualJoystick::with_counts(1, "Sticks Only", 4, 16).with_snapshots();
+/// assert_eq!(pad.get_snapshot().get_axis(Axis::TriggerLeft), MIN_AXIS_VALUE);
+/// ```
+pub struct SnapshotJoystick<J> where J:StatefulJoystick {
+	joystick: J,
+	pressed: u32,
+	released: u32,
+	held_since: Vec<Option<Instant>>,
+	/// The time of the latest event polled, and when it was polled
+	latest: Option<(Duration, Instant)>,
+	current: Snapshot,
+	previous: Snapshot
+}
+
+impl<J> SnapshotJoystick<J> where J:StatefulJoystick {
+	/// Start capturing snapshots of a joystick, starting with its current state
+	pub fn new(joystick: J) -> SnapshotJoystick<J> {
+		// Buttons that are already down count as held from the first snapshot
+		let now = Instant::now();
+		let held_since = (0..NUM_BUTTONS).map(|index| {
+			let pressed = Button::from_index(index).and_then(|button| joystick.get_button(button)).unwrap_or(false);
+			if pressed { Some(now) } else { None }
+		}).collect();
+		let current = Snapshot::capture(&joystick, 0, 0, held_since, now);
+		SnapshotJoystick {
+			held_since: current.held_since.clone(),
+			latest: None,
+			previous: current.clone(),
+			current: current,
+			pressed: 0,
+			released: 0,
+			joystick: joystick
+		}
+	}
+
+	/// Get the snapshot captured at the last update
+	pub fn get_snapshot(&self) -> &Snapshot {
+		&self.current
+	}
+
+	/// Get the snapshot captured at the update before the last one
+	pub fn get_previous(&self) -> &Snapshot {
+		&self.previous
+	}
+
+	/// Check if a button was pressed since the update before the last one
+	pub fn just_pressed(&self, button: Button) -> bool {
+		self.current.was_pressed(button)
+	}
+
+	/// Check if a button was released since the update before the last one
+	pub fn just_released(&self, button: Button) -> bool {
+		self.current.was_released(button)
+	}
+
+	/// Get how long a button had been held for at the last update, or zero if it
+	/// wasn't pressed
+	pub fn held_for(&self, button: Button) -> Duration {
+		self.current.held_for(button)
+	}
+
+	/// Record when an event was polled, and get when it happened on the clock of
+	/// the snapshots
+	fn event_instant(&mut self, time: Duration) -> Instant {
+		let now = Instant::now();
+		match self.latest {
+			Some((latest, polled)) if latest > time => polled.checked_sub(latest - time).unwrap_or(polled),
+			_ => {
+				self.latest = Some((time, now));
+				now
+			}
+		}
+	}
+
+	/// Get how far an axis moved between the last two updates
+	pub fn axis_delta(&self, axis: Axis) -> i32 {
+		self.current.get_axis(axis) as i32 - self.previous.get_axis(axis) as i32
+	}
+
+	/// Get the joystick this wraps
+	pub fn get_ref(&self) -> &J {
+		&self.joystick
+	}
+
+	/// Unwrap the joystick
+	pub fn into_inner(self) -> J {
+		self.joystick
+	}
+}
+
+impl<J> Joystick for SnapshotJoystick<J> where J:StatefulJoystick {
+	type WithState = SnapshotJoystick<<J as Joystick>::WithState>;
+	type NativeEvent = <J as Joystick>::NativeEvent;
+
+	fn open(index: u8) -> Result<SnapshotJoystick<J>, Error> {
+		Ok(SnapshotJoystick::new(try!(Joystick::open(index))))
+	}
+	delegate_joystick!(methods);
+	fn try_poll_timed(&mut self) -> Result<Option<TimedEvent>, Error> {
+		let timed = match try!(self.joystick.try_poll_timed()) {
+			Some(timed) => timed,
+			None => return Ok(None)
+		};
+		let instant = self.event_instant(timed.time);
+		match timed.event {
+			Event::ButtonPressed(button) => {
+				self.pressed |= 1 << button as u8;
+				self.held_since[button as usize] = Some(instant);
+			},
+			Event::ButtonReleased(button) => {
+				self.released |= 1 << button as u8;
+				self.held_since[button as usize] = None;
+			},
+			Event::InitialButton(button, pressed) => {
+				let held_since = &mut self.held_since[button as usize];
+				if !pressed {
+					*held_since = None
+				} else if held_since.is_none() {
+					*held_since = Some(instant)
+				}
+			},
+			_ => ()
+		}
+		Ok(Some(timed))
+	}
+	delegate_joystick!(mappings);
+	fn with_state(self) -> SnapshotJoystick<<J as Joystick>::WithState> {
+		SnapshotJoystick::new(self.joystick.with_state())
+	}
+}
+
+delegate_joystick!(impls SnapshotJoystick, StatefulJoystick);
+
+impl<J> StatefulJoystick for SnapshotJoystick<J> where J:StatefulJoystick {
+	/// This is the value of the axis in the snapshot captured at the last update
+	fn get_axis(&self, axis: Axis) -> Option<i16> {
+		Some(self.current.get_axis(axis))
+	}
+	/// This is the state of the button in the snapshot captured at the last update
+	fn get_button(&self, button: Button) -> Option<bool> {
+		Some(self.current.get_button(button))
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.joystick.get_raw_axis(index)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.joystick.get_raw_button(index)
+	}
+	fn get_axis_time(&self, axis: Axis) -> Option<Duration> {
+		self.joystick.get_axis_time(axis)
+	}
+	fn get_button_time(&self, button: Button) -> Option<Duration> {
+		self.joystick.get_button_time(button)
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.joystick.get_hat(index)
+	}
+	/// Poll every event then capture a new snapshot, which becomes the current one
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+		let (pressed, released) = (self.pressed, self.released);
+		let snapshot = Snapshot::capture(&self.joystick, pressed, released, self.held_since.clone(), Instant::now());
+		self.previous = mem::replace(&mut self.current, snapshot);
+		self.pressed = 0;
+		self.released = 0;
+	}
+}
@@ -0,0 +1,179 @@
+use futures::Stream;
+use futures::task::{Context, Poll};
+use std::collections::BTreeMap;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use tokio::io::unix::AsyncFd;
+use {DeviceJoystick, Error, Event, Joystick, JoystickManager, ManagerEvent};
+
+/// A joystick's events as a stream, which only wakes up when the joystick's
+/// file descriptor is readable
+///
+/// This needs to be made inside a Tokio runtime. The stream ends when the
+/// joystick is unplugged or an error occurs while reading it.
+///
+/// ``` rust
+/// extern crate futures;
+/// extern crate reminisce;
+/// extern crate tokio;
+/// use futures::StreamExt;
+/// use reminisce::{scan, EventStream};
+/// # fn main() {
+/// let runtime = tokio::runtime::Builder::new_current_thread().enable_io().build().unwrap();
+/// let _context = runtime.enter();
+/// for js in scan() {
+///     let mut events = EventStream::new(js).unwrap();
+///     # if false {
+///     while let Some(event) = runtime.block_on(events.next()) {
+///         println!("{:?}", event);
+///     }
+///     # }
+/// }
+/// # }
+/// ```
+pub struct EventStream<J> where J:Joystick+AsRawFd {
+	joystick: AsyncFd<J>,
+	ended: bool
+}
+
+impl<J> EventStream<J> where J:Joystick+AsRawFd {
+	/// Register the joystick with the reactor of the current Tokio runtime
+	pub fn new(joystick: J) -> Result<EventStream<J>, Error> {
+		Ok(EventStream {
+			joystick: try!(AsyncFd::new(joystick)),
+			ended: false
+		})
+	}
+
+	/// Get the joystick this reads from
+	pub fn get_ref(&self) -> &J {
+		self.joystick.get_ref()
+	}
+
+	/// Get the joystick this reads from mutably
+	pub fn get_mut(&mut self) -> &mut J {
+		self.joystick.get_mut()
+	}
+
+	/// Deregister the joystick and unwrap it
+	pub fn into_inner(self) -> J {
+		self.joystick.into_inner()
+	}
+}
+
+impl<J> Stream for EventStream<J> where J:Joystick+AsRawFd+Unpin {
+	type Item = Event;
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Event>> {
+		let this = self.get_mut();
+		while !this.ended {
+			match this.joystick.get_mut().try_poll() {
+				Ok(Some(event)) => return Poll::Ready(Some(event)),
+				Ok(None) => (),
+				Err(_) => break
+			}
+			match this.joystick.poll_read_ready_mut(cx) {
+				Poll::Ready(Ok(mut guard)) => guard.clear_ready(),
+				Poll::Ready(Err(_)) => break,
+				Poll::Pending => return Poll::Pending
+			}
+		}
+		this.ended = true;
+		Poll::Ready(None)
+	}
+}
+
+/// A joystick manager's events as a stream, including when joysticks are plugged
+/// in and out
+///
+/// This registers the manager's inotify descriptor and every joystick's file
+/// descriptor with the reactor, so it only wakes up when one of them is readable.
+/// Like `EventStream`, this needs to be made inside a Tokio runtime.
+///
+/// ``` rust
+/// extern crate futures;
+/// extern crate reminisce;
+/// extern crate tokio;
+/// use futures::StreamExt;
+/// use reminisce::{JoystickManager, ManagerEvent, ManagerStream, NativeJoystick};
+/// # fn main() {
+/// let runtime = tokio::runtime::Builder::new_current_thread().enable_io().build().unwrap();
+/// let _context = runtime.enter();
+/// if let Ok(manager) = JoystickManager::<NativeJoystick>::new() {
+///     let mut events = ManagerStream::new(manager).unwrap();
+///     # if false {
+///     while let Some(event) = runtime.block_on(events.next()) {
+///         if let ManagerEvent::Connected(index) = event {
+///             println!("Joystick #{} plugged in", index);
+///         }
+///     }
+///     # }
+/// }
+/// # }
+/// ```
+pub struct ManagerStream<J> where J:DeviceJoystick+AsRawFd {
+	// The registrations are dropped before the manager closes the descriptors, and
+	// unplugged joysticks are only closed once they have been deregistered
+	inotify: AsyncFd<RawFd>,
+	joysticks: BTreeMap<u8, AsyncFd<RawFd>>,
+	manager: JoystickManager<J>
+}
+
+impl<J> ManagerStream<J> where J:DeviceJoystick+AsRawFd {
+	/// Register the manager with the reactor of the current Tokio runtime
+	pub fn new(manager: JoystickManager<J>) -> Result<ManagerStream<J>, Error> {
+		let mut joysticks = BTreeMap::new();
+		for js in manager.joysticks() {
+			joysticks.insert(js.get_index(), try!(AsyncFd::new(js.as_raw_fd())));
+		}
+		Ok(ManagerStream {
+			inotify: try!(AsyncFd::new(manager.as_raw_fd())),
+			joysticks: joysticks,
+			manager: manager
+		})
+	}
+
+	/// Get the manager this reads from
+	pub fn get_ref(&self) -> &JoystickManager<J> {
+		&self.manager
+	}
+}
+
+impl<J> Stream for ManagerStream<J> where J:DeviceJoystick+AsRawFd+Unpin {
+	type Item = ManagerEvent;
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<ManagerEvent>> {
+		let this = self.get_mut();
+		loop {
+			if let Some((event, removed)) = this.manager.poll_detached() {
+				match event {
+					ManagerEvent::Connected(index) if !this.joysticks.contains_key(&index) => {
+						let fd = this.manager.get(index).map(|js| js.as_raw_fd());
+						if let Some(fd) = fd.and_then(|fd| AsyncFd::new(fd).ok()) {
+							this.joysticks.insert(index, fd);
+						}
+					},
+					ManagerEvent::Disconnected(index) => {
+						this.joysticks.remove(&index);
+						drop(removed);
+					},
+					_ => ()
+				}
+				return Poll::Ready(Some(event))
+			}
+			// Poll every descriptor so they all wake this task up
+			let mut ready = false;
+			for fd in Some(&this.inotify).into_iter().chain(this.joysticks.values()) {
+				match fd.poll_read_ready(cx) {
+					Poll::Ready(Ok(mut guard)) => {
+						guard.clear_ready();
+						ready = true
+					},
+					Poll::Ready(Err(_)) => return Poll::Ready(None),
+					Poll::Pending => ()
+				}
+			}
+			if !ready {
+				return Poll::Pending
+			}
+		}
+	}
+}
@@ -0,0 +1,380 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use {Axis, Button, Error, Event, HatDirection, Joystick, Rumble, StatefulJoystick, NUM_AXES, NUM_BUTTONS};
+#[cfg(feature = "mappings")]
+use mapper::{AxisRange, Source};
+
+/// An event queued by a `VirtualController`, with the time it was queued at
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VirtualEvent {
+	/// The event
+	pub event: Event,
+	/// The time on the controller's clock when the event was queued
+	pub time: Duration
+}
+
+impl From<VirtualEvent> for Event {
+	fn from(event: VirtualEvent) -> Event {
+		event.event
+	}
+}
+
+/// The state shared by a virtual joystick and its controllers
+struct Shared {
+	connected: bool,
+	time: Duration,
+	events: VecDeque<VirtualEvent>,
+	rumble: Option<(f32, f32, Duration)>
+}
+
+/// A joystick with no hardware behind it, which emits the events its controllers send
+///
+/// This makes it possible to test code built on `Joystick` without a joystick
+/// plugged in. It keeps track of its state as its events are polled, like the
+/// stateful joysticks of the other backends.
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::{Button, Event, Joystick, StatefulJoystick};
+/// use reminisce::virtual_joystick::VirtualJoystick;
+/// let mut js = VirtualJoystick::new(0, "Test Pad");
+/// let controller = js.controller();
+/// controller.press(Button::A);
+/// controller.advance(Duration::from_millis(16));
+/// controller.release(Button::A);
+/// assert_eq!(js.poll(), Some(Event::ButtonPressed(Button::A)));
+/// assert_eq!(js.get_button(Button::A), Some(true));
+/// let timed = js.poll_timed().unwrap();
+/// assert_eq!(timed.event, Event::ButtonReleased(Button::A));
+/// assert_eq!(timed.time, Duration::from_millis(16));
+/// assert_eq!(js.get_button_time(Button::A), Some(Duration::from_millis(16)));
+/// controller.disconnect();
+/// assert!(!js.is_connected());
+/// ```
+pub struct VirtualJoystick {
+	index: u8,
+	name: String,
+	num_axes: u8,
+	num_buttons: u8,
+	shared: Arc<Mutex<Shared>>,
+	axes: Vec<(i16, Option<Duration>)>,
+	buttons: Vec<(bool, Option<Duration>)>,
+	hats: BTreeMap<u8, HatDirection>,
+	#[cfg(feature = "mappings")]
+	mapping_sources: Option<(Vec<Source>, Vec<Source>)>
+}
+
+impl VirtualJoystick {
+	/// Make a virtual joystick with every `Axis` and `Button`
+	pub fn new(index: u8, name: &str) -> VirtualJoystick {
+		VirtualJoystick::with_counts(index, name, NUM_AXES, NUM_BUTTONS)
+	}
+
+	/// Make a virtual joystick with a number of axes and buttons
+	///
+	/// Raw axes and buttons past the ones that have an `Axis` or `Button` can be
+	/// used by giving more than `NUM_AXES` or `NUM_BUTTONS`.
+	pub fn with_counts(index: u8, name: &str, num_axes: u8, num_buttons: u8) -> VirtualJoystick {
+		VirtualJoystick {
+			index: index,
+			name: name.to_string(),
+			num_axes: num_axes,
+			num_buttons: num_buttons,
+			shared: Arc::new(Mutex::new(Shared {
+				connected: true,
+				time: Duration::from_millis(0),
+				events: VecDeque::new(),
+				rumble: None
+			})),
+			axes: vec![(0, None); num_axes as usize],
+			buttons: vec![(false, None); num_buttons as usize],
+			hats: BTreeMap::new(),
+			#[cfg(feature = "mappings")]
+			mapping_sources: None
+		}
+	}
+
+	/// Get a controller that can send events to this joystick
+	///
+	/// Controllers can be cloned and sent to other threads.
+	pub fn controller(&self) -> VirtualController {
+		VirtualController {
+			shared: self.shared.clone()
+		}
+	}
+
+	/// Number the axes and buttons for SDL mappings like a real device does, so
+	/// mappings written for it can be tried out
+	///
+	/// The axis that SDL mappings call `a0` is the first of `axes`, and so on.
+	///
+	/// ``` rust
+	/// use reminisce::{Axis, Button, Event, Joystick, JoystickMapper, Mapping};
+	/// use reminisce::mapper::{AxisRange, Source};
+	/// use reminisce::virtual_joystick::VirtualJoystick;
+	/// let mut js = VirtualJoystick::new(0, "X360 Controller");
+	/// // The order the Linux driver reports them in, with the guide button as a raw button
+	/// let axes = [Axis::LeftX, Axis::LeftY, Axis::TriggerLeft, Axis::RightX, Axis::RightY, Axis::TriggerRight];
+	/// let buttons = [Button::A, Button::B, Button::X, Button::Y, Button::LeftShoulder, Button::RightShoulder, Button::Select, Button::Start];
+	/// let buttons = buttons.iter().map(|&button| Source::Button(button))
+	///     .chain(vec![Source::RawButton(16), Source::Button(Button::LeftStick), Source::Button(Button::RightStick)]);
+	/// js.set_mapping_sources(axes.iter().map(|&axis| Source::Axis(axis, AxisRange::Full)).collect(), buttons.collect());
+	/// let controller = js.controller();
+	/// let mapping: Mapping = "030000005e0400008e02000014010000,X360 Controller,a:b0,b:b1,back:b6,\
+	///     dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b8,leftshoulder:b4,leftstick:b9,\
+	///     lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b10,righttrigger:a5,\
+	///     rightx:a3,righty:a4,start:b7,x:b2,y:b3,platform:Linux,".parse().unwrap();
+	/// let mut mapper = JoystickMapper::from_mapping(js, &mapping);
+	/// controller.move_axis(Axis::TriggerLeft, 32767);
+	/// controller.move_axis(Axis::RightX, 1000);
+	/// controller.press(Button::Select);
+	/// assert_eq!(mapper.poll(), Some(Event::AxisMoved(Axis::TriggerLeft, 32767)));
+	/// assert_eq!(mapper.poll(), Some(Event::AxisMoved(Axis::RightX, 1000)));
+	/// assert_eq!(mapper.poll(), Some(Event::ButtonPressed(Button::Select)));
+	/// assert_eq!(mapper.to_mapping().get("lefttrigger"), Some("a2"));
+	///
+	/// // A generic pad that only reports raw buttons
+	/// let mut js = VirtualJoystick::with_counts(1, "Generic Pad", 0, 2);
+	/// js.set_mapping_sources(Vec::new(), vec![Source::RawButton(0), Source::RawButton(1)]);
+	/// let controller = js.controller();
+	/// let mapping: Mapping = "03000000790000000600000010010000,Generic Pad,a:b1,b:b0,".parse().unwrap();
+	/// let mut mapper = JoystickMapper::from_mapping(js, &mapping);
+	/// controller.send(Event::RawButton(1, true));
+	/// assert_eq!(mapper.poll(), Some(Event::ButtonPressed(Button::A)));
+	/// ```
+	#[cfg(feature = "mappings")]
+	pub fn set_mapping_sources(&mut self, axes: Vec<Source>, buttons: Vec<Source>) {
+		self.mapping_sources = Some((axes, buttons));
+	}
+
+	/// Lock the state shared with the controllers
+	fn lock(&self) -> MutexGuard<Shared> {
+		self.shared.lock().unwrap_or_else(|err| err.into_inner())
+	}
+
+	/// Record the change in state from an event
+	fn record(&mut self, event: &VirtualEvent) {
+		let time = Some(event.time);
+		match event.event {
+			Event::InitialAxis(axis, value) | Event::AxisMoved(axis, value) =>
+				self.set_axis(axis as u8, value, time),
+			Event::RawAxisMoved(index, value) =>
+				self.set_axis(index, value, time),
+			Event::InitialButton(button, pressed) =>
+				self.set_button(button as u8, pressed, time),
+			Event::ButtonPressed(button) =>
+				self.set_button(button as u8, true, time),
+			Event::ButtonReleased(button) =>
+				self.set_button(button as u8, false, time),
+			Event::RawButton(index, pressed) =>
+				self.set_button(index, pressed, time),
+			Event::HatMoved(hat, direction) => {
+				self.hats.insert(hat, direction);
+			}
+		}
+	}
+
+	fn set_axis(&mut self, index: u8, value: i16, time: Option<Duration>) {
+		if let Some(axis) = self.axes.get_mut(index as usize) {
+			*axis = (value, time)
+		}
+	}
+
+	fn set_button(&mut self, index: u8, pressed: bool, time: Option<Duration>) {
+		if let Some(button) = self.buttons.get_mut(index as usize) {
+			*button = (pressed, time)
+		}
+	}
+}
+
+impl Joystick for VirtualJoystick {
+	type WithState = VirtualJoystick;
+	type NativeEvent = VirtualEvent;
+
+	/// Virtual joysticks can't be opened by index, so this always fails with
+	/// `Error::NotFound`
+	fn open(_index: u8) -> Result<VirtualJoystick, Error> {
+		Err(Error::NotFound)
+	}
+	fn is_connected(&self) -> bool {
+		self.lock().connected
+	}
+	fn get_id(&self) -> Cow<str> {
+		Cow::Borrowed(&self.name)
+	}
+	fn get_index(&self) -> u8 {
+		self.index
+	}
+	fn get_num_axes(&self) -> u8 {
+		self.num_axes
+	}
+	fn get_num_buttons(&self) -> u8 {
+		self.num_buttons
+	}
+	fn get_battery(&self) -> Option<f32> {
+		None
+	}
+	/// Events that were sent before the joystick was disconnected are returned
+	/// before `Error::Disconnected`
+	fn try_poll_native(&mut self) -> Result<Option<VirtualEvent>, Error> {
+		let event = {
+			let mut shared = self.lock();
+			match shared.events.pop_front() {
+				Some(event) => event,
+				None if shared.connected => return Ok(None),
+				None => return Err(Error::Disconnected)
+			}
+		};
+		self.record(&event);
+		Ok(Some(event))
+	}
+	fn get_event_time(event: &VirtualEvent) -> Duration {
+		event.time
+	}
+	/// This uses the numbering given to `set_mapping_sources`, if there is one
+	#[cfg(feature = "mappings")]
+	fn get_mapping_button(&self, number: u8) -> Option<Source> {
+		match self.mapping_sources {
+			Some((_, ref buttons)) => buttons.get(number as usize).cloned(),
+			None => Some(Button::from_index(number).map(Source::Button).unwrap_or(Source::RawButton(number)))
+		}
+	}
+	/// This uses the numbering given to `set_mapping_sources`, if there is one
+	#[cfg(feature = "mappings")]
+	fn get_mapping_axis(&self, number: u8) -> Option<Source> {
+		let full = AxisRange::Full;
+		match self.mapping_sources {
+			Some((ref axes, _)) => axes.get(number as usize).cloned(),
+			None => Some(Axis::from_index(number).map(|axis| Source::Axis(axis, full)).unwrap_or(Source::RawAxis(number, full)))
+		}
+	}
+	fn with_state(self) -> VirtualJoystick {
+		self
+	}
+}
+
+impl Rumble for VirtualJoystick {
+	fn supports_rumble(&self) -> bool {
+		true
+	}
+	/// This records the rumble so it can be checked with `VirtualController::get_rumble`
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+		self.lock().rumble = Some((strong, weak, duration));
+		Ok(())
+	}
+}
+
+impl StatefulJoystick for VirtualJoystick {
+	fn get_axis(&self, index: Axis) -> Option<i16> {
+		self.get_raw_axis(index as u8)
+	}
+	fn get_button(&self, index: Button) -> Option<bool> {
+		self.get_raw_button(index as u8)
+	}
+	fn get_raw_axis(&self, index: u8) -> Option<i16> {
+		self.axes.get(index as usize).map(|&(value, _)| value)
+	}
+	fn get_raw_button(&self, index: u8) -> Option<bool> {
+		self.buttons.get(index as usize).map(|&(pressed, _)| pressed)
+	}
+	fn get_axis_time(&self, index: Axis) -> Option<Duration> {
+		self.axes.get(index as usize).and_then(|&(_, time)| time)
+	}
+	fn get_button_time(&self, index: Button) -> Option<Duration> {
+		self.buttons.get(index as usize).and_then(|&(_, time)| time)
+	}
+	fn get_hat(&self, index: u8) -> Option<HatDirection> {
+		self.hats.get(&index).cloned()
+	}
+	fn update(&mut self) {
+		while let Some(_) = self.poll() {}
+	}
+}
+
+#[derive(Clone)]
+/// A handle that scripts what a `VirtualJoystick` does
+///
+/// Every event is stamped with the time on the controller's clock, which starts
+/// at zero and only moves when it is advanced.
+pub struct VirtualController {
+	shared: Arc<Mutex<Shared>>
+}
+
+impl VirtualController {
+	/// Lock the state shared with the joystick
+	fn lock(&self) -> MutexGuard<Shared> {
+		self.shared.lock().unwrap_or_else(|err| err.into_inner())
+	}
+
+	/// Queue an event for the joystick to emit
+	pub fn send(&self, event: Event) {
+		let mut shared = self.lock();
+		let time = shared.time;
+		shared.events.push_back(VirtualEvent {
+			event: event,
+			time: time
+		})
+	}
+
+	/// Press a button
+	pub fn press(&self, button: Button) {
+		self.send(Event::ButtonPressed(button))
+	}
+
+	/// Release a button
+	pub fn release(&self, button: Button) {
+		self.send(Event::ButtonReleased(button))
+	}
+
+	/// Move an axis to a value
+	pub fn move_axis(&self, axis: Axis, value: i16) {
+		self.send(Event::AxisMoved(axis, value))
+	}
+
+	/// Push a hat switch in a direction
+	pub fn move_hat(&self, hat: u8, direction: HatDirection) {
+		self.send(Event::HatMoved(hat, direction))
+	}
+
+	/// Move the clock forward, so events sent after this are stamped later
+	pub fn advance(&self, duration: Duration) {
+		let mut shared = self.lock();
+		shared.time = shared.time + duration
+	}
+
+	/// Set the clock to a time
+	pub fn set_time(&self, time: Duration) {
+		self.lock().time = time
+	}
+
+	/// Get the time on the clock
+	pub fn get_time(&self) -> Duration {
+		self.lock().time
+	}
+
+	/// Unplug the joystick
+	///
+	/// Events that have already been sent are still emitted, then polling fails
+	/// with `Error::Disconnected`.
+	pub fn disconnect(&self) {
+		self.lock().connected = false
+	}
+
+	/// Plug the joystick back in
+	pub fn reconnect(&self) {
+		self.lock().connected = true
+	}
+
+	/// Get the number of events the joystick hasn't emitted yet
+	pub fn pending(&self) -> usize {
+		self.lock().events.len()
+	}
+
+	/// Get the strong and weak magnitudes and duration of the last rumble played
+	/// on the joystick, if it has rumbled
+	pub fn get_rumble(&self) -> Option<(f32, f32, Duration)> {
+		self.lock().rumble
+	}
+}
@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+use {Error, Event, Joystick, TimedEvent};
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+use std::os::unix::io::AsRawFd;
+
+/// A joystick that a thread can sleep on until it emits an event
+///
+/// This is every joystick with a file descriptor on Linux, and every joystick
+/// on the other backends.
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+pub trait Waitable : Joystick + AsRawFd {}
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+impl<J> Waitable for J where J:Joystick+AsRawFd {}
+
+/// A joystick that a thread can sleep on until it emits an event
+///
+/// This is every joystick with a file descriptor on Linux, and every joystick
+/// on the other backends.
+#[cfg(not(all(target_os = "linux", not(feature = "sdl"))))]
+pub trait Waitable : Joystick {}
+#[cfg(not(all(target_os = "linux", not(feature = "sdl"))))]
+impl<J> Waitable for J where J:Joystick {}
+
+/// How long to sleep between polls on backends that can't be waited on
+#[cfg(not(any(all(target_os = "linux", not(feature = "sdl")), feature = "sdl")))]
+static POLL_INTERVAL_MS: u64 = 4;
+
+/// Poll the joysticks until one of them emits an event or fails, sleeping with
+/// `sleep` in between and starting from the joystick at `next`
+///
+/// `sleep` returns whether to keep waiting, or the index of a joystick that hung up
+/// while it slept.
+fn wait_with<J, F>(next: &mut usize, joysticks: &mut [J], timeout: Option<Duration>, mut sleep: F) -> Option<(usize, Result<TimedEvent, Error>)>
+	where J:Joystick, F:FnMut(&[J], Option<Duration>) -> Result<bool, usize> {
+	let deadline = timeout.map(|timeout| Instant::now() + timeout);
+	loop {
+		let count = joysticks.len();
+		let start = *next;
+		for offset in 0..count {
+			let index = (start + offset) % count;
+			let result = match joysticks[index].try_poll_timed() {
+				Ok(None) => continue,
+				Ok(Some(timed)) => Ok(timed),
+				Err(err) => Err(err)
+			};
+			*next = index + 1;
+			return Some((index, result))
+		}
+		let remaining = match deadline {
+			Some(deadline) => {
+				let now = Instant::now();
+				if now >= deadline {
+					return None
+				}
+				Some(deadline - now)
+			},
+			None => None
+		};
+		match sleep(joysticks, remaining) {
+			Ok(true) => (),
+			Ok(false) => return None,
+			Err(index) => {
+				// A joystick that hung up without failing would wake this up forever
+				let result = match joysticks[index].try_poll_timed() {
+					Ok(Some(timed)) => Ok(timed),
+					Ok(None) => Err(Error::Disconnected),
+					Err(err) => Err(err)
+				};
+				*next = index + 1;
+				return Some((index, result))
+			}
+		}
+	}
+}
+
+#[cfg(any(target_os = "linux", feature = "sdl"))]
+/// Convert a timeout into milliseconds, rounding up so waiting doesn't end early
+fn timeout_millis(timeout: Option<Duration>) -> i32 {
+	match timeout {
+		Some(timeout) => {
+			let millis = timeout.as_secs() * 1000 + (timeout.subsec_nanos() as u64 + 999999) / 1000000;
+			if millis > i32::max_value() as u64 { i32::max_value() } else { millis as i32 }
+		},
+		None => -1
+	}
+}
+
+/// Sleep on the file descriptors of the joysticks with `poll`
+#[cfg(all(target_os = "linux", not(feature = "sdl")))]
+fn wait_native<J>(next: &mut usize, joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<TimedEvent, Error>)> where J:Waitable {
+	use libc::{nfds_t, poll, pollfd, EINTR, POLLERR, POLLHUP, POLLIN, POLLNVAL};
+	use std::io;
+	wait_with(next, joysticks, timeout, |joysticks, timeout| {
+		let mut fds: Vec<_> = joysticks.iter().map(|js| pollfd {
+			fd: js.as_raw_fd(),
+			events: POLLIN,
+			revents: 0
+		}).collect();
+		if fds.is_empty() {
+			return Ok(false)
+		}
+		match unsafe { poll(fds.as_mut_ptr(), fds.len() as nfds_t, timeout_millis(timeout)) } {
+			-1 => Ok(io::Error::last_os_error().raw_os_error() == Some(EINTR)),
+			0 => Ok(false),
+			_ => match fds.iter().position(|fd| fd.revents & (POLLHUP | POLLERR | POLLNVAL) != 0) {
+				Some(index) => Err(index),
+				None => Ok(true)
+			}
+		}
+	})
+}
+
+/// Sleep until SDL has an event with `SDL_WaitEventTimeout`
+#[cfg(feature = "sdl")]
+fn wait_native<J>(next: &mut usize, joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<TimedEvent, Error>)> where J:Waitable {
+	use sdl2::sys::event::SDL_WaitEventTimeout;
+	use std::ptr;
+	wait_with(next, joysticks, timeout, |_, timeout| {
+		// Passing no event leaves it in the queue for the joystick to poll
+		Ok(unsafe { SDL_WaitEventTimeout(ptr::null_mut(), timeout_millis(timeout)) == 1 })
+	})
+}
+
+/// Poll every few milliseconds, because this backend can't be waited on
+#[cfg(not(any(all(target_os = "linux", not(feature = "sdl")), feature = "sdl")))]
+fn wait_native<J>(next: &mut usize, joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<TimedEvent, Error>)> where J:Waitable {
+	use std::thread;
+	wait_with(next, joysticks, timeout, |_, timeout| {
+		let interval = Duration::from_millis(POLL_INTERVAL_MS);
+		thread::sleep(timeout.map(|timeout| timeout.min(interval)).unwrap_or(interval));
+		Ok(true)
+	})
+}
+
+/// Waits on joysticks, and remembers which one had the last event
+///
+/// The joysticks are polled starting from the one after the joystick that had the
+/// last event, so a busy joystick can't hide the others. Every thread or loop that
+/// waits should have its own `Waiter`, and keep using it for the same joysticks.
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::{scan, Error, Joystick, Waiter};
+/// let mut joysticks = scan();
+/// let mut waiter = Waiter::new();
+/// while let Some((index, result)) = waiter.wait(&mut joysticks, Some(Duration::from_millis(10))) {
+///     match result {
+///         Ok(event) => println!("{}: {:?}", joysticks[index].get_id(), event),
+///         Err(Error::Disconnected) => {
+///             joysticks.remove(index);
+///         },
+///         Err(err) => println!("{}", err)
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Waiter {
+	/// The index of the joystick to poll first
+	next: usize
+}
+
+impl Waiter {
+	/// Create a waiter that starts from the first joystick
+	pub fn new() -> Waiter {
+		Waiter {
+			next: 0
+		}
+	}
+
+	/// Block the thread until any of the joysticks emits an event or fails, or the
+	/// timeout passes, and return the index of the joystick with the event or error
+	///
+	/// If the timeout is none this waits forever, unless there are no joysticks to
+	/// wait on. On Linux this sleeps on the file descriptors of the joysticks with
+	/// `poll` and with SDL this uses `SDL_WaitEventTimeout`, so the thread doesn't
+	/// use any CPU while it waits. Other backends are polled every few milliseconds.
+	///
+	/// A joystick that is unplugged is returned with `Error::Disconnected`, and keeps
+	/// failing until it is removed from the joysticks being waited on.
+	pub fn wait<J>(&mut self, joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<Event, Error>)> where J:Waitable {
+		self.wait_timed(joysticks, timeout).map(|(index, result)| (index, result.map(|timed| timed.event)))
+	}
+
+	/// Block the thread until any of the joysticks emits an event or the timeout
+	/// passes, and return the index of the joystick with the event and the time it
+	/// was emitted, or the error it failed with
+	///
+	/// This waits the same way as `wait`.
+	pub fn wait_timed<J>(&mut self, joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<TimedEvent, Error>)> where J:Waitable {
+		wait_native(&mut self.next, joysticks, timeout)
+	}
+}
+
+/// Block the thread until any of the joysticks emits an event or fails, or the
+/// timeout passes, and return the index of the joystick with the event or error
+///
+/// This waits like `Waiter::wait`, but always polls the first joystick first, so
+/// a busy joystick can hide the ones after it when this is called in a loop.
+///
+/// ``` rust
+/// use std::time::Duration;
+/// use reminisce::{scan, wait};
+/// let mut joysticks = scan();
+/// if let Some((index, result)) = wait(&mut joysticks, Some(Duration::from_millis(10))) {
+///     println!("#{}: {:?}", index, result);
+/// }
+/// ```
+pub fn wait<J>(joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<Event, Error>)> where J:Waitable {
+	Waiter::new().wait(joysticks, timeout)
+}
+
+/// Block the thread until any of the joysticks emits an event or the timeout
+/// passes, and return the index of the joystick with the event and the time it
+/// was emitted, or the error it failed with
+///
+/// This waits like `Waiter::wait_timed`, always polling the first joystick first.
+pub fn wait_timed<J>(joysticks: &mut [J], timeout: Option<Duration>) -> Option<(usize, Result<TimedEvent, Error>)> where J:Waitable {
+	Waiter::new().wait_timed(joysticks, timeout)
+}
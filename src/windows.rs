@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::collections::VecDeque;
-use std::error::Error;
 use std::mem;
+use std::time::Duration;
 // I tried
 // It's a bit hard to write this without a Windows pc on hand...
 // But this should work on Windows Vista, 7, and 8
@@ -10,8 +10,15 @@ extern "stdcall" {
 	fn XInputGetCapabilities(index: u32, flags: u32, capabilities: *mut Capabilities) -> i32;
 	fn XInputGetState(index: u32, state: *mut State) -> i32;
 	fn XInputGetBatteryInformation(index: u32, ty: u8, information: *mut Battery) -> i32;
+	fn XInputSetState(index: u32, vibration: *mut Vibration) -> i32;
 
 }
+#[link(name = "kernel32")]
+extern "stdcall" {
+	fn GetTickCount() -> u32;
+}
+
+static ERROR_DEVICE_NOT_CONNECTED: i32 = 1167;
 
 #[repr(u8)]
 enum BatteryLevel {
@@ -82,8 +89,8 @@ struct Gamepad {
 }
 #[repr(C)]
 struct Vibration {
-	left_motor_speed: i32,
-	right_motor_speed: i32
+	left_motor_speed: u16,
+	right_motor_speed: u16
 }
 
 /// Scan for joysticks
@@ -95,13 +102,18 @@ pub struct NativeJoystick {
 	index: u8,
 	last: Gamepad,
 	last_packet: i32,
-	events: VecDeque<::Event>
+	events: VecDeque<::TimedEvent>,
+	rumble_until: Option<u32>
+}
+
+/// Convert the event
+pub fn convert_event(event: ::TimedEvent) -> ::Event {
+	event.event
 }
 impl ::Joystick for NativeJoystick {
 	type WithState = NativeJoystick;
-	type NativeEvent = ::Event;
-	type Error = Error;
-	fn open(index: u8) -> Result<NativeJoystick, &'static str> {
+	type NativeEvent = ::TimedEvent;
+	fn open(index: u8) -> Result<NativeJoystick, ::Error> {
 		unsafe {
 			let mut caps: Capabilities = mem::uninitialized();
 			let code = XInputGetCapabilities(index as u32, 0, &mut caps);
@@ -110,17 +122,25 @@ impl ::Joystick for NativeJoystick {
 					index: index,
 					last: caps.gamepad,
 					last_packet: 0,
-					events: VecDeque::with_capacity(10)
+					events: VecDeque::with_capacity(10),
+					rumble_until: None
 				})
+			} else if code == ERROR_DEVICE_NOT_CONNECTED {
+				Err(::Error::NotFound)
 			} else {
-				Err(Error::from_os_error(code))
+				Err(::std::io::Error::from_raw_os_error(code).into())
 			}
 		}
 	}
-	fn poll_native(&mut self) -> Option<::Event> {
+	fn try_poll_native(&mut self) -> Result<Option<::TimedEvent>, ::Error> {
 		use ::StatefulJoystick;
 		self.update();
-		self.events.pop_back()
+		Ok(self.events.pop_back())
+	}
+	/// XInput doesn't timestamp its state, so this is the tick count in
+	/// milliseconds when the state was read
+	fn get_event_time(event: &::TimedEvent) -> Duration {
+		event.time
 	}
 	fn is_connected(&self) -> bool {
 		true
@@ -142,6 +162,19 @@ impl ::Joystick for NativeJoystick {
 			}
 		}
 	}
+	/// XInput doesn't report if a battery is charging, so this is either wired or
+	/// discharging
+	fn get_battery_state(&self) -> Option<::BatteryState> {
+		unsafe {
+			let mut battery = mem::uninitialized();
+			XInputGetBatteryInformation(self.index as u32, 0, &mut battery);
+			match battery._type {
+				BatteryType::Disconnected | BatteryType::Unknown => None,
+				BatteryType::Wired => Some(::BatteryState::Wired),
+				_ => Some(::BatteryState::Discharging)
+			}
+		}
+	}
 	fn get_id(&self) -> Cow<str> {
 		"XInput Device".into()
 	}
@@ -158,21 +191,52 @@ impl ::Joystick for NativeJoystick {
 		self
 	}
 }
+impl NativeJoystick {
+	fn push_event(&mut self, event: ::Event) {
+		let time = Duration::from_millis(unsafe { GetTickCount() } as u64);
+		self.events.push_back(::TimedEvent { event: event, time: time, source: self.index })
+	}
+}
+impl ::Rumble for NativeJoystick {
+	/// Every XInput controller has a strong motor on the left and a weak motor on the right
+	fn supports_rumble(&self) -> bool {
+		true
+	}
+	/// XInput keeps the motors running until they are stopped, so they are stopped
+	/// by `update` once the duration has passed
+	fn rumble(&mut self, strong: f32, weak: f32, duration: Duration) -> Result<(), ::Error> {
+		let magnitude = |value: f32| (value.max(0.0).min(1.0) * 65535.0) as u16;
+		let mut vibration = Vibration {
+			left_motor_speed: magnitude(strong),
+			right_motor_speed: magnitude(weak)
+		};
+		let code = unsafe { XInputSetState(self.index as u32, &mut vibration) };
+		if code == 0 {
+			let millis = duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1000000;
+			self.rumble_until = Some(unsafe { GetTickCount() }.wrapping_add(millis as u32));
+			Ok(())
+		} else if code == ERROR_DEVICE_NOT_CONNECTED {
+			Err(::Error::Disconnected)
+		} else {
+			Err(::std::io::Error::from_raw_os_error(code).into())
+		}
+	}
+}
 macro_rules! event{
 	(button $this:expr, $now:expr, $last:expr, $btn:expr, $id:expr) => (
 		{
 			let now_btn = $now.buttons.contains($btn);
 			let last_btn = $last.buttons.contains($btn);
 			if now_btn && !last_btn {
-				$this.events.push_back(::Event::ButtonPressed($id))
+				$this.push_event(::Event::ButtonPressed($id))
 			} else if !now_btn && last_btn {
-				$this.events.push_back(::Event::ButtonReleased($id))
+				$this.push_event(::Event::ButtonReleased($id))
 			}
 		}
 	);
 	(axis $this:expr, $now:expr, $last:expr, $field:ident, $id:expr) => (
 		if $now.$field != $last.$field {
-			$this.events.push_back(::Event::AxisMoved($id, $now.$field))
+			$this.push_event(::Event::AxisMoved($id, $now.$field))
 		}
 	);
 	(axes $this:expr, $now:expr, $last:expr, $($field:ident => $id:expr),+) => ({
@@ -209,6 +273,13 @@ impl ::StatefulJoystick for NativeJoystick {
 		bits.map(|v| self.last.buttons.contains(v))
 	}
 	fn update(&mut self) {
+		if let Some(until) = self.rumble_until {
+			if unsafe { GetTickCount() }.wrapping_sub(until) < 0x80000000 {
+				use ::Rumble;
+				let _ = self.stop_rumble();
+				self.rumble_until = None;
+			}
+		}
 		let state = unsafe {
 			let mut state: State = mem::uninitialized();
 			XInputGetState(self.index as u32, &mut state);